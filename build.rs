@@ -0,0 +1,47 @@
+//! Detects whether `CStr` and `c_char` are available in `core::ffi`.
+//!
+//! They were moved there in Rust 1.64. On older toolchains, `zstr!()`
+//! falls back to emitting paths into `std` instead.
+//!
+//! Also detects whether `unsafe extern` blocks and `#[unsafe(...)]`
+//! attributes are supported, which they are since Rust 1.82, and which
+//! are required in code using edition 2024.
+
+use std::env;
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let minor = match rustc_minor_version() {
+        Some(minor) => minor,
+        None => return,
+    };
+
+    if minor >= 80 {
+        println!("cargo:rustc-check-cfg=cfg(zstr_no_core_ffi)");
+        println!("cargo:rustc-check-cfg=cfg(zstr_no_unsafe_attributes)");
+    }
+
+    if minor < 64 {
+        println!("cargo:rustc-cfg=zstr_no_core_ffi");
+    }
+
+    if minor < 82 {
+        println!("cargo:rustc-cfg=zstr_no_unsafe_attributes");
+    }
+}
+
+/// Returns the minor version of the compiler, e.g. `64` for `rustc 1.64.0`.
+fn rustc_minor_version() -> Option<u32> {
+    let rustc = env::var_os("RUSTC")?;
+    let output = Command::new(rustc).arg("--version").output().ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+    let mut pieces = version.split('.');
+
+    if pieces.next() != Some("rustc 1") {
+        return None;
+    }
+
+    pieces.next()?.parse().ok()
+}
@@ -0,0 +1,41 @@
+//! Null-terminated arrays of pointers to C strings.
+
+use proc_macro2::{ Span, TokenStream as TokenStream2 };
+use syn::{ Error, Lit, Token };
+use syn::parse::{ Parser, ParseStream };
+use syn::punctuated::Punctuated;
+use quote::quote_spanned;
+use crate::ffi::FfiRoot;
+use crate::literal_cstr;
+
+/// Performs the actual expansion of `zstr_array!()`.
+pub fn expand_zstr_array(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let (root, literals) = Parser::parse2(
+        |input: ParseStream| Ok((
+            input.parse::<FfiRoot>()?,
+            Punctuated::<Lit, Token![,]>::parse_terminated(input)?,
+        )),
+        input,
+    )?;
+
+    let span = Span::call_site();
+    let c_char = root.c_char(span);
+    let pointers = literals
+        .iter()
+        .map(|literal| {
+            let cstr = literal_cstr(&root, literal)?;
+            Ok(quote_spanned!(literal.span() => #cstr.as_ptr()))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    // Expand to an expression of type `&'static [*const c_char]`.
+    Ok(quote_spanned!{
+        span => {
+            const ARRAY: &'static [*const #c_char] = &[
+                #(#pointers,)*
+                ::core::ptr::null(),
+            ];
+            ARRAY
+        }
+    })
+}
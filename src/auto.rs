@@ -0,0 +1,294 @@
+//! Rewriting string literal arguments of foreign function calls.
+
+use std::iter;
+use proc_macro2::{ Delimiter, Group, Literal, TokenStream as TokenStream2, TokenTree };
+use syn::{ braced, Abi, Attribute, Error, Expr, ExprCall, ForeignItemFn, Ident, Item, Lit, Path, Token, Visibility };
+use syn::parse::{ Parse, Parser, ParseStream };
+use syn::punctuated::Punctuated;
+use syn::visit_mut::{ self, VisitMut };
+use quote::{ quote_spanned, ToTokens };
+use crate::ffi::FfiRoot;
+use crate::literal_cstr;
+
+/// The arguments of `#[zstr::auto]`.
+struct AutoArgs {
+    root: FfiRoot,
+    prefixes: Vec<Path>,
+}
+
+impl Parse for AutoArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let root = input.parse()?;
+        let prefixes = Punctuated::<Path, Token![,]>::parse_terminated(input)?;
+
+        Ok(AutoArgs { root, prefixes: prefixes.into_iter().collect() })
+    }
+}
+
+/// Collects the names of the functions declared in `extern` blocks.
+#[derive(Default)]
+struct ForeignFns {
+    names: Vec<Ident>,
+}
+
+impl VisitMut for ForeignFns {
+    fn visit_foreign_item_fn_mut(&mut self, item: &mut ForeignItemFn) {
+        self.names.push(item.sig.ident.clone());
+    }
+}
+
+/// The name of the macro that `unsafe extern` blocks are replaced with
+/// while the annotated item is being processed.
+const PLACEHOLDER: &str = "__zstr_unsafe_extern";
+
+/// `unsafe extern` blocks (and the `safe` qualifier of items in them),
+/// which are required in edition 2024, are not supported by syn. Thus,
+/// they are cut out of the item before it is parsed, and replaced with
+/// `__zstr_unsafe_extern!(index);` placeholders, which are substituted
+/// back when the item is emitted. The names of the functions declared in
+/// them are parsed by hand.
+#[derive(Default)]
+struct UnsafeForeignMods {
+    blocks: Vec<TokenStream2>,
+    names: Vec<Ident>,
+}
+
+impl UnsafeForeignMods {
+    /// Replaces all `unsafe extern` blocks in `tokens` with placeholders.
+    fn extract(&mut self, tokens: TokenStream2) -> Result<TokenStream2, Error> {
+        let trees: Vec<TokenTree> = tokens.into_iter().collect();
+        let mut output = TokenStream2::new();
+        let mut i = 0;
+
+        while i < trees.len() {
+            let len = unsafe_foreign_mod_len(&trees[i..]);
+
+            if len > 0 {
+                let block: TokenStream2 = trees[i..i + len].iter().cloned().collect();
+                let names = unsafe_foreign_fns.parse2(block.clone()).map_err(|error| {
+                    let message = format!("`#[zstr::auto]` could not parse this `unsafe extern` block: {}", error);
+                    Error::new(error.span(), message)
+                })?;
+                let span = trees[i].span();
+                let placeholder = Ident::new(PLACEHOLDER, span);
+                let index = Literal::usize_unsuffixed(self.blocks.len());
+
+                output.extend(quote_spanned!(span => #placeholder!(#index);));
+                self.blocks.push(block);
+                self.names.extend(names);
+                i += len;
+                continue;
+            }
+
+            output.extend(iter::once(match &trees[i] {
+                TokenTree::Group(group) => {
+                    let mut new = Group::new(group.delimiter(), self.extract(group.stream())?);
+                    new.set_span(group.span());
+                    TokenTree::Group(new)
+                }
+                tree => tree.clone(),
+            }));
+            i += 1;
+        }
+
+        Ok(output)
+    }
+
+    /// Substitutes the original blocks for the placeholders in `tokens`.
+    fn restore(&self, tokens: TokenStream2) -> TokenStream2 {
+        let trees: Vec<TokenTree> = tokens.into_iter().collect();
+        let mut output = TokenStream2::new();
+        let mut i = 0;
+
+        while i < trees.len() {
+            if let Some(block) = self.placeholder_at(&trees[i..]) {
+                output.extend(block.clone());
+                i += 4;
+                continue;
+            }
+
+            output.extend(iter::once(match &trees[i] {
+                TokenTree::Group(group) => {
+                    let mut new = Group::new(group.delimiter(), self.restore(group.stream()));
+                    new.set_span(group.span());
+                    TokenTree::Group(new)
+                }
+                tree => tree.clone(),
+            }));
+            i += 1;
+        }
+
+        output
+    }
+
+    /// Returns the original block if `trees` starts with a placeholder.
+    fn placeholder_at(&self, trees: &[TokenTree]) -> Option<&TokenStream2> {
+        match trees {
+            [TokenTree::Ident(ident), TokenTree::Punct(bang), TokenTree::Group(group), TokenTree::Punct(semi), ..]
+                if ident == PLACEHOLDER && bang.as_char() == '!' && semi.as_char() == ';' =>
+            {
+                let index: usize = group.stream().to_string().parse().ok()?;
+                self.blocks.get(index)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Returns the number of token trees making up the `unsafe extern "ABI"
+/// { ... }` block that `trees` starts with, or 0 if it does not start
+/// with one. The ABI is optional.
+fn unsafe_foreign_mod_len(trees: &[TokenTree]) -> usize {
+    let is_block = |tree: &TokenTree| matches!(tree, TokenTree::Group(group) if group.delimiter() == Delimiter::Brace);
+
+    match trees {
+        [TokenTree::Ident(kw_unsafe), TokenTree::Ident(kw_extern), rest @ ..]
+            if kw_unsafe == "unsafe" && kw_extern == "extern" =>
+        {
+            match rest {
+                [block, ..] if is_block(block) => 3,
+                [TokenTree::Literal(_), block, ..] if is_block(block) => 4,
+                _ => 0,
+            }
+        }
+        _ => 0,
+    }
+}
+
+/// Parses an `unsafe extern "ABI" { ... }` block, and returns the names
+/// of the functions declared in it. Only the names are parsed; the rest
+/// of each item is skipped up to the terminating semicolon.
+fn unsafe_foreign_fns(input: ParseStream) -> syn::Result<Vec<Ident>> {
+    input.parse::<Token![unsafe]>()?;
+    input.parse::<Abi>()?;
+
+    let content;
+    braced!(content in input);
+    content.call(Attribute::parse_inner)?;
+
+    let mut names = Vec::new();
+
+    while !content.is_empty() {
+        content.call(Attribute::parse_outer)?;
+        content.parse::<Visibility>()?;
+
+        // Items may be qualified as `safe` or `unsafe`.
+        if content.peek(Token![unsafe]) {
+            content.parse::<Token![unsafe]>()?;
+        } else if content.peek(Ident) && content.fork().parse::<Ident>()? == "safe" {
+            content.parse::<Ident>()?;
+        }
+
+        if content.peek(Token![fn]) {
+            content.parse::<Token![fn]>()?;
+            names.push(content.parse()?);
+        }
+
+        while !content.is_empty() && !content.peek(Token![;]) {
+            content.parse::<TokenTree>()?;
+        }
+
+        content.parse::<Token![;]>()?;
+    }
+
+    Ok(names)
+}
+
+/// Rewrites string literal arguments of calls to foreign functions.
+struct Rewriter {
+    root: FfiRoot,
+    prefixes: Vec<Path>,
+    foreign: Vec<Ident>,
+    error: Option<Error>,
+}
+
+impl Rewriter {
+    /// Returns `true` if `func` is the path of a foreign function, i.e.
+    /// the bare name of one declared in the annotated item, or one starting
+    /// with any of the path prefixes given as arguments of the attribute.
+    fn is_foreign(&self, func: &Expr) -> bool {
+        let path = match func {
+            Expr::Path(expr) if expr.qself.is_none() => &expr.path,
+            _ => return false,
+        };
+
+        // Qualified paths may refer to a Rust function of the same name,
+        // so they are only matched against the prefixes.
+        let declared = path.leading_colon.is_none()
+            && path.segments.len() == 1
+            && self.foreign.contains(&path.segments[0].ident);
+
+        declared || self.prefixes.iter().any(|prefix| {
+            prefix.segments.len() <= path.segments.len()
+                && prefix.leading_colon.is_some() == path.leading_colon.is_some()
+                && prefix.segments.iter().zip(&path.segments).all(|(p, s)| p.ident == s.ident)
+        })
+    }
+
+    /// Adds an error to the ones already found.
+    fn push_error(&mut self, error: Error) {
+        match &mut self.error {
+            Some(existing) => existing.combine(error),
+            None => self.error = Some(error),
+        }
+    }
+}
+
+impl VisitMut for Rewriter {
+    fn visit_expr_call_mut(&mut self, call: &mut ExprCall) {
+        visit_mut::visit_expr_call_mut(self, call);
+
+        if !self.is_foreign(&call.func) {
+            return;
+        }
+
+        for arg in call.args.iter_mut() {
+            let literal = match arg {
+                Expr::Lit(expr) if matches!(expr.lit, Lit::Str(_) | Lit::ByteStr(_)) => &expr.lit,
+                _ => continue,
+            };
+
+            let span = literal.span();
+
+            // Invalid literals are replaced by a null pointer, so that
+            // they are not also reported as type errors. Valid ones are
+            // defined as constants, because item bodies do not inherit
+            // the `unsafe` context of the call, which would make the
+            // `unsafe` block of the C string redundant.
+            let ty = self.root.cstr(span);
+            *arg = Expr::Verbatim(match literal_cstr(&self.root, literal) {
+                Ok(cstr) => quote_spanned!(span => {
+                    const CSTR: &#ty = #cstr;
+                    CSTR
+                }.as_ptr()),
+                Err(error) => {
+                    self.push_error(error);
+                    quote_spanned!(span => ::core::ptr::null())
+                }
+            });
+        }
+    }
+}
+
+/// Performs the actual expansion of `#[zstr::auto]`.
+pub fn expand_auto(args: TokenStream2, item: TokenStream2) -> Result<TokenStream2, Error> {
+    let AutoArgs { root, prefixes } = AutoArgs::parse.parse2(args)?;
+    let mut unsafe_foreign_mods = UnsafeForeignMods::default();
+    let mut item: Item = syn::parse2(unsafe_foreign_mods.extract(item)?)?;
+
+    let mut foreign = ForeignFns { names: unsafe_foreign_mods.names.clone() };
+    foreign.visit_item_mut(&mut item);
+
+    let mut rewriter = Rewriter { root, prefixes, foreign: foreign.names, error: None };
+    rewriter.visit_item_mut(&mut item);
+
+    // Emit the item even if some literals are invalid, so that the
+    // errors are not followed by spurious unresolved name errors.
+    let mut tokens = unsafe_foreign_mods.restore(item.into_token_stream());
+
+    if let Some(error) = rewriter.error {
+        tokens.extend(error.into_compile_error());
+    }
+
+    Ok(tokens)
+}
@@ -0,0 +1,112 @@
+//! Table-driven encoders for legacy CJK multi-byte encodings.
+//!
+//! The tables are generated from the codecs of the Python standard
+//! library by `tables/generate.py`, which also documents their format.
+
+use std::convert::TryFrom;
+
+/// Two-byte (and non-ASCII single-byte) Shift_JIS codes.
+static SHIFT_JIS: &[u8] = include_bytes!("tables/shift_jis.bin");
+/// Two-byte GBK codes.
+static GBK: &[u8] = include_bytes!("tables/gbk.bin");
+/// Two-byte GB 18030 codes that differ from GBK.
+static GB18030: &[u8] = include_bytes!("tables/gb18030.bin");
+/// Runs of BMP characters encoded as four bytes in GB 18030.
+static GB18030_RANGES: &[u8] = include_bytes!("tables/gb18030_ranges.bin");
+/// Two-byte EUC-KR codes.
+static EUC_KR: &[u8] = include_bytes!("tables/euc_kr.bin");
+
+/// Encodes a character in Shift_JIS.
+pub fn encode_shift_jis(c: char, buf: &mut Vec<u8>) -> Option<()> {
+    encode_with_table(SHIFT_JIS, c, buf)
+}
+
+/// Encodes a character in GBK.
+pub fn encode_gbk(c: char, buf: &mut Vec<u8>) -> Option<()> {
+    encode_with_table(GBK, c, buf)
+}
+
+/// Encodes a character in EUC-KR.
+pub fn encode_euc_kr(c: char, buf: &mut Vec<u8>) -> Option<()> {
+    encode_with_table(EUC_KR, c, buf)
+}
+
+/// Encodes a character in GB 18030. Unlike the other encodings, this
+/// one can represent every Unicode scalar value.
+pub fn encode_gb18030(c: char, buf: &mut Vec<u8>) -> Option<()> {
+    if encode_with_table(GB18030, c, buf).is_some() || encode_with_table(GBK, c, buf).is_some() {
+        return Some(());
+    }
+
+    let cp = u32::from(c);
+    let index = if cp >= 0x1_0000 {
+        189_000 + (cp - 0x1_0000)
+    } else {
+        // Find the last run starting at or before `cp`.
+        let count = GB18030_RANGES.len() / 8;
+        let run = partition_point(count, |i| read_u32(GB18030_RANGES, i * 8) <= cp).checked_sub(1)?;
+        let start = read_u32(GB18030_RANGES, run * 8);
+        let start_index = read_u32(GB18030_RANGES, run * 8 + 4);
+        start_index + (cp - start)
+    };
+
+    let b4 = index % 10;
+    let b3 = index / 10 % 126;
+    let b2 = index / 10 / 126 % 10;
+    let b1 = index / 10 / 126 / 10;
+
+    buf.extend_from_slice(&[b1 as u8 + 0x81, b2 as u8 + 0x30, b3 as u8 + 0x81, b4 as u8 + 0x30]);
+    Some(())
+}
+
+/// Encodes ASCII characters as-is, and looks up any other character
+/// in a table of `(u16, u16)` records.
+fn encode_with_table(table: &[u8], c: char, buf: &mut Vec<u8>) -> Option<()> {
+    if c.is_ascii() {
+        buf.push(c as u8);
+        return Some(());
+    }
+
+    let cp = u16::try_from(u32::from(c)).ok()?;
+    let count = table.len() / 4;
+    let index = partition_point(count, |i| read_u16(table, i * 4) < cp);
+
+    if index >= count || read_u16(table, index * 4) != cp {
+        return None;
+    }
+
+    match read_u16(table, index * 4 + 2) {
+        code @ 0x00..=0xFF => buf.push(code as u8),
+        code => buf.extend_from_slice(&code.to_be_bytes()),
+    }
+
+    Some(())
+}
+
+/// Returns the number of leading indices in `0..count` for which `pred`
+/// holds, assuming that it holds for a prefix of the indices only.
+fn partition_point<F: Fn(usize) -> bool>(count: usize, pred: F) -> usize {
+    let (mut lo, mut hi) = (0, count);
+
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+
+        if pred(mid) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+
+    lo
+}
+
+/// Reads a big-endian `u16` at byte offset `offset`.
+fn read_u16(table: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([table[offset], table[offset + 1]])
+}
+
+/// Reads a big-endian `u32` at byte offset `offset`.
+fn read_u32(table: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([table[offset], table[offset + 1], table[offset + 2], table[offset + 3]])
+}
@@ -0,0 +1,56 @@
+//! Compile-time concatenation of literals into a single C string.
+
+use proc_macro2::{ Span, TokenStream as TokenStream2 };
+use syn::{ Error, Lit, Token };
+use syn::parse::{ Parser, ParseStream };
+use syn::punctuated::Punctuated;
+use crate::ffi::FfiRoot;
+use crate::cstr_expr;
+
+/// Performs the actual expansion of `zstr_concat!()`.
+pub fn expand_zstr_concat(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let (root, literals) = Parser::parse2(
+        |input: ParseStream| Ok((
+            input.parse::<FfiRoot>()?,
+            Punctuated::<Lit, Token![,]>::parse_terminated(input)?,
+        )),
+        input,
+    )?;
+
+    let mut bytes = Vec::new();
+
+    for (index, literal) in literals.iter().enumerate() {
+        let piece = argument_bytes(literal)?;
+
+        if let Some(offset) = piece.iter().position(|&b| b == 0x00) {
+            let message = format!(
+                "C string contains an embedded NUL byte at index {} of argument {}",
+                offset,
+                index + 1,
+            );
+            return Err(Error::new(literal.span(), message));
+        }
+
+        bytes.extend(piece);
+    }
+
+    let span = literals.first().map_or_else(Span::call_site, Lit::span);
+
+    Ok(cstr_expr(&root, bytes, span))
+}
+
+/// Returns the bytes that a single argument of `zstr_concat!()` stands for.
+fn argument_bytes(literal: &Lit) -> Result<Vec<u8>, Error> {
+    let bytes = match literal {
+        Lit::Str(lit) => lit.value().into_bytes(),
+        Lit::ByteStr(lit) => lit.value(),
+        Lit::Byte(lit) => vec![lit.value()],
+        Lit::Char(lit) => lit.value().to_string().into_bytes(),
+        Lit::Int(lit) => lit.base10_digits().as_bytes().to_vec(),
+        Lit::Float(lit) => lit.base10_digits().as_bytes().to_vec(),
+        Lit::Bool(lit) => lit.value.to_string().into_bytes(),
+        Lit::Verbatim(_) => return Err(Error::new(literal.span(), "unsupported literal")),
+    };
+
+    Ok(bytes)
+}
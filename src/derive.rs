@@ -0,0 +1,292 @@
+//! Derive macros mapping enum variants to C string names.
+
+use proc_macro2::{ Literal, Span, TokenStream as TokenStream2 };
+use syn::{ parse_quote, Attribute, Data, DeriveInput, Error, Fields, GenericParam, Ident, LitStr, Token, Variant };
+use syn::parse::ParseStream;
+use quote::{ quote_spanned, ToTokens };
+use crate::ffi::FfiRoot;
+use crate::{ ensure_no_nul, cstr_expr };
+
+/// A case convention for `#[zstr(rename_all = "...")]`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    /// The names of the rules, as accepted by `rename_all`.
+    const NAMES: &'static [(&'static str, RenameRule)] = &[
+        ("lowercase", RenameRule::Lower),
+        ("UPPERCASE", RenameRule::Upper),
+        ("PascalCase", RenameRule::Pascal),
+        ("camelCase", RenameRule::Camel),
+        ("snake_case", RenameRule::Snake),
+        ("SCREAMING_SNAKE_CASE", RenameRule::ScreamingSnake),
+        ("kebab-case", RenameRule::Kebab),
+        ("SCREAMING-KEBAB-CASE", RenameRule::ScreamingKebab),
+    ];
+
+    /// Applies the rule to the name of a variant, which is assumed to be
+    /// written in `PascalCase`, as is conventional in Rust.
+    fn apply(self, variant: &str) -> String {
+        let separated = |separator: char| {
+            let mut name = String::new();
+            for (i, c) in variant.char_indices() {
+                if c.is_uppercase() && i > 0 {
+                    name.push(separator);
+                }
+                name.extend(c.to_lowercase());
+            }
+            name
+        };
+
+        match self {
+            RenameRule::Lower => variant.to_lowercase(),
+            RenameRule::Upper => variant.to_uppercase(),
+            RenameRule::Pascal => variant.to_owned(),
+            RenameRule::Camel => {
+                let mut chars = variant.chars();
+                chars.next().map_or_else(String::new, |first| first.to_lowercase().chain(chars).collect())
+            }
+            RenameRule::Snake => separated('_'),
+            RenameRule::ScreamingSnake => separated('_').to_uppercase(),
+            RenameRule::Kebab => separated('-'),
+            RenameRule::ScreamingKebab => separated('-').to_uppercase(),
+        }
+    }
+}
+
+/// The `#[zstr(...)]` attributes of an enum or one of its variants.
+#[derive(Default)]
+struct ZstrAttrs {
+    root: Option<FfiRoot>,
+    rename: Option<LitStr>,
+    rename_all: Option<RenameRule>,
+}
+
+impl ZstrAttrs {
+    /// Parses all `#[zstr(...)]` attributes in `attrs`.
+    fn parse(attrs: &[Attribute]) -> Result<Self, Error> {
+        let mut result = ZstrAttrs::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("zstr")) {
+            attr.parse_args_with(|input: ParseStream| {
+                while !input.is_empty() {
+                    result.parse_one(input)?;
+
+                    if !input.is_empty() {
+                        input.parse::<Token![,]>()?;
+                    }
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(result)
+    }
+
+    /// Parses a single `key = value` pair.
+    fn parse_one(&mut self, input: ParseStream) -> syn::Result<()> {
+        if input.peek(Token![crate]) {
+            let span = input.parse::<Token![crate]>()?.span;
+            input.parse::<Token![=]>()?;
+            return set_once(&mut self.root, FfiRoot::Custom(input.parse()?), span, "crate");
+        }
+
+        let key: Ident = input.parse()?;
+        input.parse::<Token![=]>()?;
+
+        if key == "rename" {
+            set_once(&mut self.rename, input.parse()?, key.span(), "rename")
+        } else if key == "rename_all" {
+            let value: LitStr = input.parse()?;
+            let rule = RenameRule::NAMES
+                .iter()
+                .find(|&&(name, _)| value.value() == name)
+                .map(|&(_, rule)| rule)
+                .ok_or_else(|| {
+                    let names: Vec<_> = RenameRule::NAMES.iter().map(|&(name, _)| name).collect();
+                    let message = format!("unknown case convention; expected one of: {}", names.join(", "));
+                    Error::new(value.span(), message)
+                })?;
+            set_once(&mut self.rename_all, rule, key.span(), "rename_all")
+        } else {
+            let message = format!("unknown attribute `{}`; expected one of: crate, rename, rename_all", key);
+            Err(Error::new(key.span(), message))
+        }
+    }
+}
+
+/// Sets an attribute value, unless it has already been set.
+fn set_once<T>(slot: &mut Option<T>, value: T, span: Span, name: &str) -> syn::Result<()> {
+    if slot.is_some() {
+        return Err(Error::new(span, format!("duplicate `{}` attribute", name)));
+    }
+
+    *slot = Some(value);
+    Ok(())
+}
+
+/// A variant of an enum together with its C string name.
+pub struct NamedVariant<'a> {
+    /// The variant itself.
+    pub variant: &'a Variant,
+    /// The name of the variant, validated not to contain NUL bytes.
+    pub name: String,
+    /// The span to report errors about the name at.
+    pub span: Span,
+}
+
+/// The information about an enum needed by the derive macros.
+pub struct NamedEnum<'a> {
+    /// Where to look up `CStr`.
+    pub root: FfiRoot,
+    /// The variants, in declaration order.
+    pub variants: Vec<NamedVariant<'a>>,
+}
+
+impl<'a> NamedEnum<'a> {
+    /// Collects and validates the names of the variants of an enum.
+    /// `derive` is the name of the derive macro, used in diagnostics.
+    pub fn new(input: &'a DeriveInput, derive: &str) -> Result<Self, Error> {
+        let data = match &input.data {
+            Data::Enum(data) => data,
+            _ => {
+                let message = format!("`{}` can only be derived for enums", derive);
+                return Err(Error::new_spanned(&input.ident, message));
+            }
+        };
+
+        let attrs = ZstrAttrs::parse(&input.attrs)?;
+
+        if let Some(rename) = attrs.rename {
+            return Err(Error::new(rename.span(), "`rename` is only allowed on variants"));
+        }
+
+        let rule = attrs.rename_all.unwrap_or(RenameRule::Pascal);
+        let variants = data.variants
+            .iter()
+            .map(|variant| {
+                let variant_attrs = ZstrAttrs::parse(&variant.attrs)?;
+
+                if variant_attrs.root.is_some() || variant_attrs.rename_all.is_some() {
+                    let message = "only `rename` is allowed on variants";
+                    return Err(Error::new_spanned(&variant.ident, message));
+                }
+
+                let (name, span) = match variant_attrs.rename {
+                    Some(rename) => (rename.value(), rename.span()),
+                    None => (rule.apply(&variant.ident.to_string()), variant.ident.span()),
+                };
+
+                ensure_no_nul(name.as_bytes(), span, "C string", "byte")?;
+
+                Ok(NamedVariant { variant, name, span })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(NamedEnum {
+            root: attrs.root.unwrap_or(FfiRoot::Default),
+            variants,
+        })
+    }
+}
+
+/// Performs the actual expansion of `#[derive(ZstrName)]`.
+pub fn expand_zstr_name(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let named = NamedEnum::new(&input, "ZstrName")?;
+    let span = Span::call_site();
+    let ty = named.root.cstr(span);
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let arms = named.variants.iter().map(|variant| {
+        let ident = &variant.variant.ident;
+        // The generated code is not spanned at the variant, so that lints
+        // about it are not reported at (and applied to) the user's enum.
+        let cstr = cstr_expr(&named.root, variant.name.clone().into_bytes(), span);
+        quote_spanned!(span => Self::#ident { .. } => #cstr,)
+    });
+
+    Ok(quote_spanned!{
+        span =>
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Returns the name of this variant as a C string.
+            pub const fn as_cstr(&self) -> &'static #ty {
+                match *self {
+                    #(#arms)*
+                }
+            }
+        }
+    })
+}
+
+/// Performs the actual expansion of `#[derive(ZstrFromName)]`.
+pub fn expand_zstr_from_name(mut input: DeriveInput) -> Result<TokenStream2, Error> {
+    let named = NamedEnum::new(&input, "ZstrFromName")?;
+    let span = Span::call_site();
+    let ty = named.root.cstr(span);
+
+    if let Some(variant) = named.variants.iter().find(|v| !matches!(v.variant.fields, Fields::Unit)) {
+        let message = "`ZstrFromName` can only be derived for enums whose variants have no fields";
+        return Err(Error::new_spanned(&variant.variant.ident, message));
+    }
+
+    // Sort the names so that they can be looked up using binary search.
+    let mut sorted: Vec<(&[u8], usize)> = named.variants
+        .iter()
+        .enumerate()
+        .map(|(index, variant)| (variant.name.as_bytes(), index))
+        .collect();
+    sorted.sort_unstable();
+
+    if let Some(pair) = sorted.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+        let variant = &named.variants[pair[0].1.max(pair[1].1)];
+        let message = format!("duplicate C string name {:?}", variant.name);
+        return Err(Error::new(variant.span, message));
+    }
+
+    let names: Vec<_> = sorted.iter().map(|&(name, _)| Literal::byte_string(name)).collect();
+    let indices: Vec<_> = sorted.iter().map(|&(_, index)| index).collect();
+    let arms: Vec<_> = named.variants
+        .iter()
+        .enumerate()
+        .map(|(index, variant)| {
+            let ident = &variant.variant.ident;
+            quote_spanned!(variant.span => #index => ::core::result::Result::Ok(Self::#ident),)
+        })
+        .collect();
+
+    // The impl needs a lifetime for the borrowed error value, in addition
+    // to the generic parameters of the enum itself.
+    let ident = input.ident.clone();
+    let ty_generics = input.generics.split_for_impl().1.to_token_stream();
+    input.generics.params.insert(0, GenericParam::Lifetime(parse_quote!('__zstr)));
+    let (impl_generics, _, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote_spanned!{
+        span =>
+        impl #impl_generics ::core::convert::TryFrom<&'__zstr #ty> for #ident #ty_generics #where_clause {
+            type Error = &'__zstr #ty;
+
+            fn try_from(name: &'__zstr #ty) -> ::core::result::Result<Self, Self::Error> {
+                const NAMES: &[(&[u8], usize)] = &[#((#names, #indices)),*];
+
+                match NAMES.binary_search_by(|&(candidate, _)| candidate.cmp(name.to_bytes())) {
+                    ::core::result::Result::Ok(found) => match NAMES[found].1 {
+                        #(#arms)*
+                        _ => ::core::result::Result::Err(name),
+                    },
+                    ::core::result::Result::Err(_) => ::core::result::Result::Err(name),
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,221 @@
+//! Transcoding string literals to legacy narrow character encodings.
+//!
+//! The CJK multi-byte encodings are implemented in the `cjk` module.
+
+use proc_macro2::Span;
+use syn::{ Error, Ident, Lit, Token };
+use syn::parse::{ Parse, ParseStream };
+use crate::{ cjk, literal_bytes, literal_subspan, ensure_no_nul };
+
+/// The encoding of the bytes of a generated C string.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Encoding {
+    /// UTF-8, i.e. the bytes of the literal as-is.
+    Utf8,
+    /// 7-bit ASCII.
+    Ascii,
+    /// ISO-8859-1 (Latin-1).
+    Latin1,
+    /// Windows-1252, a superset of the printable characters of Latin-1.
+    Cp1252,
+    /// Shift_JIS (JIS X 0201 and JIS X 0208).
+    ShiftJis,
+    /// GBK.
+    Gbk,
+    /// GB 18030, a superset of GBK covering all of Unicode.
+    Gb18030,
+    /// EUC-KR (KS X 1001).
+    EucKr,
+    /// Java's modified UTF-8, as used by JNI.
+    Mutf8,
+}
+
+impl Encoding {
+    /// The names of the encodings, as accepted by `zstr!()`.
+    const NAMES: &'static [(&'static str, Encoding)] = &[
+        ("utf8", Encoding::Utf8),
+        ("ascii", Encoding::Ascii),
+        ("latin1", Encoding::Latin1),
+        ("cp1252", Encoding::Cp1252),
+        ("shift_jis", Encoding::ShiftJis),
+        ("gbk", Encoding::Gbk),
+        ("gb18030", Encoding::Gb18030),
+        ("euc_kr", Encoding::EucKr),
+        ("mutf8", Encoding::Mutf8),
+    ];
+
+    /// A human-readable name of the encoding, for use in diagnostics.
+    fn display_name(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Ascii => "ASCII",
+            Encoding::Latin1 => "Latin-1",
+            Encoding::Cp1252 => "Windows-1252",
+            Encoding::ShiftJis => "Shift_JIS",
+            Encoding::Gbk => "GBK",
+            Encoding::Gb18030 => "GB 18030",
+            Encoding::EucKr => "EUC-KR",
+            Encoding::Mutf8 => "modified UTF-8",
+        }
+    }
+
+    /// Encodes a single character, or returns `None` if the character
+    /// cannot be represented in this encoding.
+    fn encode_char(self, c: char, buf: &mut Vec<u8>) -> Option<()> {
+        let byte = match self {
+            Encoding::Utf8 => {
+                buf.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                return Some(());
+            }
+            Encoding::Ascii if c.is_ascii() => c as u8,
+            Encoding::Latin1 if u32::from(c) <= 0xFF => c as u8,
+            Encoding::Cp1252 => cp1252_byte(c)?,
+            Encoding::ShiftJis => return cjk::encode_shift_jis(c, buf),
+            Encoding::Gbk => return cjk::encode_gbk(c, buf),
+            Encoding::Gb18030 => return cjk::encode_gb18030(c, buf),
+            Encoding::EucKr => return cjk::encode_euc_kr(c, buf),
+            Encoding::Mutf8 => {
+                encode_mutf8(c, buf);
+                return Some(());
+            }
+            _ => return None,
+        };
+
+        buf.push(byte);
+        Some(())
+    }
+}
+
+/// Maps a character to its Windows-1252 code, if there is one.
+fn cp1252_byte(c: char) -> Option<u8> {
+    let byte = match c {
+        '\u{0000}'..='\u{007F}' | '\u{00A0}'..='\u{00FF}' => c as u8,
+        '\u{20AC}' => 0x80,
+        '\u{201A}' => 0x82,
+        '\u{0192}' => 0x83,
+        '\u{201E}' => 0x84,
+        '\u{2026}' => 0x85,
+        '\u{2020}' => 0x86,
+        '\u{2021}' => 0x87,
+        '\u{02C6}' => 0x88,
+        '\u{2030}' => 0x89,
+        '\u{0160}' => 0x8A,
+        '\u{2039}' => 0x8B,
+        '\u{0152}' => 0x8C,
+        '\u{017D}' => 0x8E,
+        '\u{2018}' => 0x91,
+        '\u{2019}' => 0x92,
+        '\u{201C}' => 0x93,
+        '\u{201D}' => 0x94,
+        '\u{2022}' => 0x95,
+        '\u{2013}' => 0x96,
+        '\u{2014}' => 0x97,
+        '\u{02DC}' => 0x98,
+        '\u{2122}' => 0x99,
+        '\u{0161}' => 0x9A,
+        '\u{203A}' => 0x9B,
+        '\u{0153}' => 0x9C,
+        '\u{017E}' => 0x9E,
+        '\u{0178}' => 0x9F,
+        _ => return None,
+    };
+
+    Some(byte)
+}
+
+/// Encodes a character in modified UTF-8. This differs from UTF-8 in that
+/// U+0000 is encoded as the overlong sequence `C0 80`, so that encoded
+/// strings never contain 0 bytes, and supplementary characters are
+/// encoded as a surrogate pair, each half taking 3 bytes.
+fn encode_mutf8(c: char, buf: &mut Vec<u8>) {
+    if c == '\0' {
+        buf.extend_from_slice(&[0xC0, 0x80]);
+        return;
+    }
+
+    for unit in c.encode_utf16(&mut [0; 2]) {
+        let unit = u32::from(*unit);
+
+        match unit {
+            0x0000..=0x007F => buf.push(unit as u8),
+            0x0080..=0x07FF => buf.extend_from_slice(&[
+                0xC0 | (unit >> 6) as u8,
+                0x80 | (unit & 0x3F) as u8,
+            ]),
+            _ => buf.extend_from_slice(&[
+                0xE0 | (unit >> 12) as u8,
+                0x80 | (unit >> 6 & 0x3F) as u8,
+                0x80 | (unit & 0x3F) as u8,
+            ]),
+        }
+    }
+}
+
+/// An optional `encoding:` prefix before a string literal.
+pub struct EncodingPrefix {
+    /// The selected encoding, or `None` if no prefix was given.
+    pub encoding: Option<(Encoding, Span)>,
+}
+
+impl Parse for EncodingPrefix {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if !input.peek(Ident) || !input.peek2(Token![:]) || input.peek2(Token![::]) {
+            return Ok(EncodingPrefix { encoding: None });
+        }
+
+        let ident: Ident = input.parse()?;
+        input.parse::<Token![:]>()?;
+
+        let encoding = Encoding::NAMES
+            .iter()
+            .find(|&&(name, _)| ident == name)
+            .map(|&(_, encoding)| encoding)
+            .ok_or_else(|| {
+                let names: Vec<_> = Encoding::NAMES.iter().map(|&(name, _)| name).collect();
+                let message = format!("unknown encoding `{}`; expected one of: {}", ident, names.join(", "));
+                Error::new(ident.span(), message)
+            })?;
+
+        Ok(EncodingPrefix { encoding: Some((encoding, ident.span())) })
+    }
+}
+
+/// Returns the bytes of a string or byte string literal transcoded to
+/// `encoding`, without the terminating 0, ensuring that it does not
+/// contain any NUL bytes. Byte strings are only accepted with UTF-8,
+/// which is the default, and they are not transcoded.
+pub fn encoded_literal_bytes(encoding: Option<(Encoding, Span)>, literal: &Lit) -> Result<Vec<u8>, Error> {
+    let (encoding, encoding_span) = match encoding {
+        Some(encoding) => encoding,
+        None => return literal_bytes(literal),
+    };
+
+    let string = match literal {
+        Lit::Str(lit) => lit.value(),
+        _ if encoding == Encoding::Utf8 => return literal_bytes(literal),
+        _ => {
+            let message = format!("{} encoding can only be applied to string literals", encoding.display_name());
+            return Err(Error::new(encoding_span, message));
+        }
+    };
+
+    let mut bytes = Vec::with_capacity(string.len());
+
+    for (index, c) in string.char_indices() {
+        if encoding.encode_char(c, &mut bytes).is_none() {
+            let message = format!(
+                "character {:?} (U+{:04X}) at index {} cannot be represented in {}",
+                c,
+                u32::from(c),
+                index,
+                encoding.display_name(),
+            );
+            let span = literal_subspan(literal, index..index + c.len_utf8());
+            return Err(Error::new(span, message));
+        }
+    }
+
+    ensure_no_nul(&bytes, literal.span(), "C string", "byte")?;
+
+    Ok(bytes)
+}
@@ -0,0 +1,101 @@
+//! Compile-time environment variables as C strings.
+
+use std::env::{ self, VarError };
+use proc_macro2::{ Span, TokenStream as TokenStream2 };
+use syn::{ Error, LitStr, Token };
+use syn::parse::{ Parser, ParseStream };
+use quote::quote_spanned;
+use crate::ffi::FfiRoot;
+use crate::{ ensure_no_nul, cstr_expr };
+
+/// Performs the actual expansion of `env_zstr!()`.
+pub fn expand_env_zstr(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let (root, name, message) = Parser::parse2(
+        |input: ParseStream| {
+            let root = input.parse::<FfiRoot>()?;
+            let name = input.parse::<LitStr>()?;
+            let message = if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+                let message = input.parse::<LitStr>()?;
+                input.parse::<Option<Token![,]>>()?;
+                Some(message)
+            } else {
+                None
+            };
+            Ok((root, name, message))
+        },
+        input,
+    )?;
+    let span = name.span();
+
+    let value = match read_var(&name)? {
+        Some(value) => value,
+        None => {
+            let message = message.map_or_else(
+                || format!("environment variable `{}` not defined at compile time", name.value()),
+                |message| message.value(),
+            );
+            return Err(Error::new(Span::call_site(), message));
+        }
+    };
+
+    let cstr = cstr_expr(&root, value, span);
+
+    Ok(quote_spanned!{
+        span => {
+            // Make the compiler aware of the dependency on the variable,
+            // so that the crate is rebuilt when its value changes.
+            const _: &str = env!(#name);
+            #cstr
+        }
+    })
+}
+
+/// Performs the actual expansion of `option_env_zstr!()`.
+pub fn expand_option_env_zstr(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let (root, name) = Parser::parse2(
+        |input: ParseStream| {
+            let root = input.parse::<FfiRoot>()?;
+            let name = input.parse::<LitStr>()?;
+            input.parse::<Option<Token![,]>>()?;
+            Ok((root, name))
+        },
+        input,
+    )?;
+    let span = name.span();
+    let ty = root.cstr(span);
+
+    let value = match read_var(&name)? {
+        Some(value) => {
+            let cstr = cstr_expr(&root, value, span);
+            quote_spanned!(span => ::core::option::Option::Some(#cstr))
+        }
+        None => quote_spanned!(span => ::core::option::Option::<&'static #ty>::None),
+    };
+
+    Ok(quote_spanned!{
+        span => {
+            // Make the compiler aware of the dependency on the variable.
+            const _: ::core::option::Option<&str> = option_env!(#name);
+            #value
+        }
+    })
+}
+
+/// Reads the environment variable named by `name`, and ensures that its
+/// value, if any, is valid Unicode and does not contain NUL bytes.
+fn read_var(name: &LitStr) -> Result<Option<Vec<u8>>, Error> {
+    let span = name.span();
+
+    match env::var(name.value()) {
+        Ok(value) => {
+            let what = format!("environment variable `{}`", name.value());
+            ensure_no_nul(value.as_bytes(), span, &what, "byte")?;
+            Ok(Some(value.into_bytes()))
+        }
+        Err(VarError::NotPresent) => Ok(None),
+        Err(VarError::NotUnicode(_)) => {
+            let message = format!("environment variable `{}` is not valid Unicode", name.value());
+            Err(Error::new(span, message))
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! Paths to the FFI types that generated code refers to.
+
+use proc_macro2::{ Ident, Span, TokenStream as TokenStream2 };
+use syn::{ Path, Token };
+use syn::parse::{ Parse, ParseStream };
+use quote::quote_spanned;
+
+/// The root of the paths to `CStr` and the C primitive types (`c_char`,
+/// `c_int`, etc.) in the generated code.
+///
+/// By default, this is `core`, so that the macros can be used from
+/// `#![no_std]` crates. On toolchains older than Rust 1.64, where these
+/// types are not yet available in `core::ffi`, it falls back to `std`.
+/// It can be overridden by the invoker using a leading `crate = path,`
+/// argument, in which case the types are looked up in `path::ffi`.
+pub enum FfiRoot {
+    /// Use `core::ffi`, or `std` on older toolchains.
+    Default,
+    /// Use the `ffi` module of the crate at the specified path.
+    Custom(Path),
+}
+
+impl FfiRoot {
+    /// The path to the `CStr` type.
+    pub fn cstr(&self, span: Span) -> TokenStream2 {
+        match self {
+            FfiRoot::Default if cfg!(zstr_no_core_ffi) => quote_spanned!(span => ::std::ffi::CStr),
+            FfiRoot::Default => quote_spanned!(span => ::core::ffi::CStr),
+            FfiRoot::Custom(path) => quote_spanned!(span => #path::ffi::CStr),
+        }
+    }
+
+    /// The path to the `c_char` type.
+    pub fn c_char(&self, span: Span) -> TokenStream2 {
+        self.c_type("c_char", span)
+    }
+
+    /// The path to one of the C primitive types, e.g. `c_int` or `c_void`.
+    pub fn c_type(&self, name: &str, span: Span) -> TokenStream2 {
+        let name = Ident::new(name, span);
+
+        match self {
+            FfiRoot::Default if cfg!(zstr_no_core_ffi) => quote_spanned!(span => ::std::os::raw::#name),
+            FfiRoot::Default => quote_spanned!(span => ::core::ffi::#name),
+            FfiRoot::Custom(path) => quote_spanned!(span => #path::ffi::#name),
+        }
+    }
+}
+
+impl Parse for FfiRoot {
+    /// Parses an optional `crate = path,` prefix. The comma may be
+    /// omitted if nothing else follows.
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if !input.peek(Token![crate]) || !input.peek2(Token![=]) {
+            return Ok(FfiRoot::Default);
+        }
+
+        input.parse::<Token![crate]>()?;
+        input.parse::<Token![=]>()?;
+        let path = input.parse()?;
+
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+        }
+
+        Ok(FfiRoot::Custom(path))
+    }
+}
@@ -0,0 +1,135 @@
+//! Fixed-size, NUL-padded `c_char` arrays, e.g. for C struct fields.
+
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{ Error, Ident, Lit, LitInt, Token };
+use syn::parse::{ Parse, Parser, ParseStream };
+use quote::quote_spanned;
+use crate::ffi::FfiRoot;
+use crate::encoding::{ EncodingPrefix, encoded_literal_bytes };
+
+/// What to do if the string does not fit into the array.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Overflow {
+    /// Reject the string with a compile error.
+    Error,
+    /// Truncate the string, keeping the terminating 0, and warn.
+    Truncate,
+}
+
+/// The input of `zarray!()`.
+struct ZarrayInput {
+    root: FfiRoot,
+    size: LitInt,
+    overflow: Overflow,
+    pad: u8,
+    prefix: EncodingPrefix,
+    literal: Lit,
+}
+
+impl Parse for ZarrayInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let root = input.parse()?;
+        let size = input.parse()?;
+        input.parse::<Token![,]>()?;
+
+        let mut overflow = None;
+        let mut pad = None;
+
+        // Options are identifiers followed by `,` or `=`, which
+        // distinguishes them from an encoding prefix like `latin1:`.
+        while input.peek(Ident) && (input.peek2(Token![,]) || input.peek2(Token![=])) {
+            let option: Ident = input.parse()?;
+
+            if option == "error" || option == "truncate" {
+                if overflow.is_some() {
+                    return Err(Error::new(option.span(), "overflow policy specified more than once"));
+                }
+                overflow = Some(if option == "error" { Overflow::Error } else { Overflow::Truncate });
+            } else if option == "pad_with" {
+                if pad.is_some() {
+                    return Err(Error::new(option.span(), "`pad_with` specified more than once"));
+                }
+                input.parse::<Token![=]>()?;
+                pad = Some(match input.parse()? {
+                    Lit::Byte(lit) => lit.value(),
+                    Lit::Int(lit) => lit.base10_parse()?,
+                    lit => return Err(Error::new(lit.span(), "expected a byte or integer literal")),
+                });
+            } else {
+                let message = format!("unknown option `{}`; expected one of: error, truncate, pad_with", option);
+                return Err(Error::new(option.span(), message));
+            }
+
+            input.parse::<Token![,]>()?;
+        }
+
+        let prefix = input.parse()?;
+        let literal = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+
+        Ok(ZarrayInput {
+            root,
+            size,
+            overflow: overflow.unwrap_or(Overflow::Error),
+            pad: pad.unwrap_or(0x00),
+            prefix,
+            literal,
+        })
+    }
+}
+
+/// Performs the actual expansion of `zarray!()`.
+pub fn expand_zarray(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let ZarrayInput { root, size, overflow, pad, prefix, literal } = ZarrayInput::parse.parse2(input)?;
+    let span = literal.span();
+    let size_value: usize = size.base10_parse()?;
+    let mut bytes = encoded_literal_bytes(prefix.encoding, &literal)?;
+    let mut warning = TokenStream2::new();
+
+    if size_value == 0 {
+        return Err(Error::new(size.span(), "array must have room for at least the NUL terminator"));
+    }
+
+    // The string must fit together with its terminating 0.
+    if bytes.len() >= size_value {
+        if overflow == Overflow::Error {
+            let message = format!(
+                "C string of length {} does not fit into an array of size {} with its NUL terminator",
+                bytes.len(),
+                size_value,
+            );
+            return Err(Error::new(span, message));
+        }
+
+        // Procedural macros can't emit warnings directly on stable,
+        // so this is done by using a deprecated item instead.
+        let note = format!(
+            "string literal truncated from {} to {} bytes to fit into an array of size {}",
+            bytes.len(),
+            size_value - 1,
+            size_value,
+        );
+        warning = quote_spanned!{
+            span =>
+            #[deprecated(note = #note)]
+            const fn zarray_truncated() {}
+            zarray_truncated();
+        };
+
+        bytes.truncate(size_value - 1);
+    }
+
+    // Add the terminating 0, then pad the rest of the array.
+    bytes.push(0x00);
+    bytes.resize(size_value, pad);
+
+    let c_char = root.c_char(span);
+
+    // Expand to an expression of type `[c_char; N]`.
+    Ok(quote_spanned!{
+        span => {
+            #warning
+            [#(#bytes as #c_char),*]
+        }
+    })
+}
@@ -0,0 +1,328 @@
+//! Parsing and validation of C `printf()` format strings.
+
+use std::ops::Range;
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{ Attribute, Error, Ident, Lit, Token, Visibility };
+use syn::parse::{ Parse, Parser, ParseStream };
+use quote::quote_spanned;
+use crate::ffi::FfiRoot;
+use crate::{ literal_bytes, literal_subspan, cstr_expr };
+
+/// The C type of an argument consumed by a conversion specification.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ArgKind {
+    /// `signed char` (`%hhd`), promoted to `int`.
+    SChar,
+    /// `unsigned char` (`%hhu`), promoted to `int`.
+    UChar,
+    /// `short` (`%hd`), promoted to `int`.
+    Short,
+    /// `unsigned short` (`%hu`), promoted to `int`.
+    UShort,
+    /// `int` (`%d`, `%c`, and `*` widths and precisions).
+    Int,
+    /// `unsigned int` (`%u`, `%x`, `%o`).
+    UInt,
+    /// `long` (`%ld`).
+    Long,
+    /// `unsigned long` (`%lu`).
+    ULong,
+    /// `long long` (`%lld`).
+    LongLong,
+    /// `unsigned long long` (`%llu`).
+    ULongLong,
+    /// `intmax_t` (`%jd`).
+    IntMax,
+    /// `uintmax_t` (`%ju`).
+    UIntMax,
+    /// `size_t` (`%zu`).
+    Size,
+    /// The signed counterpart of `size_t` (`%zd`).
+    SSize,
+    /// `ptrdiff_t` (`%td`).
+    PtrDiff,
+    /// `double` (`%f`, `%e`, `%g`, `%a`).
+    Double,
+    /// `long double` (`%Lf`).
+    LongDouble,
+    /// `wint_t` (`%lc`).
+    WInt,
+    /// `const char *` (`%s`).
+    Str,
+    /// `const wchar_t *` (`%ls`).
+    WideStr,
+    /// `void *` (`%p`).
+    Pointer,
+}
+
+impl ArgKind {
+    /// The name of the C type, for use in diagnostics and documentation.
+    pub fn c_type(self) -> &'static str {
+        match self {
+            ArgKind::SChar => "signed char",
+            ArgKind::UChar => "unsigned char",
+            ArgKind::Short => "short",
+            ArgKind::UShort => "unsigned short",
+            ArgKind::Int => "int",
+            ArgKind::UInt => "unsigned int",
+            ArgKind::Long => "long",
+            ArgKind::ULong => "unsigned long",
+            ArgKind::LongLong => "long long",
+            ArgKind::ULongLong => "unsigned long long",
+            ArgKind::IntMax => "intmax_t",
+            ArgKind::UIntMax => "uintmax_t",
+            ArgKind::Size => "size_t",
+            ArgKind::SSize => "ssize_t",
+            ArgKind::PtrDiff => "ptrdiff_t",
+            ArgKind::Double => "double",
+            ArgKind::LongDouble => "long double",
+            ArgKind::WInt => "wint_t",
+            ArgKind::Str => "const char *",
+            ArgKind::WideStr => "const wchar_t *",
+            ArgKind::Pointer => "void *",
+        }
+    }
+}
+
+/// A single conversion specification, i.e. a directive starting with `%`.
+#[derive(Clone, Debug)]
+pub struct Directive {
+    /// The byte range of the directive within the format string.
+    pub range: Range<usize>,
+    /// The arguments consumed by the directive, in order: the field width
+    /// and the precision if they are given as `*`, then the value itself.
+    pub args: Vec<ArgKind>,
+}
+
+/// An error found in a format string.
+#[derive(Clone, Debug)]
+pub struct FormatError {
+    /// The byte range of the offending part of the format string.
+    pub range: Range<usize>,
+    /// Describes what is wrong.
+    pub message: String,
+}
+
+/// A length modifier of a conversion specification.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Length {
+    None,
+    Hh,
+    H,
+    L,
+    Ll,
+    J,
+    Z,
+    T,
+    BigL,
+}
+
+/// Parses all conversion specifications in a format string.
+pub fn parse_format(format: &[u8]) -> Result<Vec<Directive>, FormatError> {
+    let mut directives = Vec::new();
+    let mut i = 0;
+
+    while i < format.len() {
+        if format[i] == b'%' {
+            let directive = parse_directive(format, i)?;
+            i = directive.range.end;
+            directives.push(directive);
+        } else {
+            i += 1;
+        }
+    }
+
+    Ok(directives)
+}
+
+/// Parses the conversion specification starting at `start`, which must
+/// be the index of a `%` character.
+fn parse_directive(format: &[u8], start: usize) -> Result<Directive, FormatError> {
+    let error = |end: usize, message: &str| FormatError {
+        range: start..end.min(format.len()).max(start + 1),
+        message: message.into(),
+    };
+    let digits = |mut i: usize| {
+        while matches!(format.get(i), Some(b'0'..=b'9')) {
+            i += 1;
+        }
+        i
+    };
+
+    let mut args = Vec::new();
+    let mut i = start + 1;
+
+    // A literal percent sign must not have any flags, width, etc.
+    if format.get(i) == Some(&b'%') {
+        return Ok(Directive { range: start..i + 1, args });
+    }
+
+    // Flags
+    while let Some(b'-' | b'+' | b' ' | b'#' | b'0' | b'\'') = format.get(i) {
+        i += 1;
+    }
+
+    // Field width
+    if format.get(i) == Some(&b'*') {
+        args.push(ArgKind::Int);
+        i += 1;
+    } else {
+        i = digits(i);
+    }
+
+    if format.get(i) == Some(&b'$') {
+        return Err(error(i + 1, "positional arguments are not supported"));
+    }
+
+    // Precision
+    if format.get(i) == Some(&b'.') {
+        i += 1;
+
+        if format.get(i) == Some(&b'*') {
+            args.push(ArgKind::Int);
+            i += 1;
+        } else {
+            i = digits(i);
+        }
+    }
+
+    // Length modifier
+    let (length, len) = match (format.get(i), format.get(i + 1)) {
+        (Some(b'h'), Some(b'h')) => (Length::Hh, 2),
+        (Some(b'l'), Some(b'l')) => (Length::Ll, 2),
+        (Some(b'h'), _) => (Length::H, 1),
+        (Some(b'l'), _) => (Length::L, 1),
+        (Some(b'j'), _) => (Length::J, 1),
+        (Some(b'z'), _) => (Length::Z, 1),
+        (Some(b't'), _) => (Length::T, 1),
+        (Some(b'L'), _) => (Length::BigL, 1),
+        _ => (Length::None, 0),
+    };
+    i += len;
+
+    // Conversion specifier
+    let conversion = match format.get(i) {
+        Some(&c) => c,
+        None => return Err(error(i, "incomplete conversion specification at end of format string")),
+    };
+    let end = i + 1;
+
+    let kind = match (conversion, length) {
+        (b'd' | b'i', Length::Hh) => ArgKind::SChar,
+        (b'd' | b'i', Length::H) => ArgKind::Short,
+        (b'd' | b'i', Length::None) => ArgKind::Int,
+        (b'd' | b'i', Length::L) => ArgKind::Long,
+        (b'd' | b'i', Length::Ll) => ArgKind::LongLong,
+        (b'd' | b'i', Length::J) => ArgKind::IntMax,
+        (b'd' | b'i', Length::Z) => ArgKind::SSize,
+        (b'd' | b'i', Length::T) => ArgKind::PtrDiff,
+        (b'o' | b'u' | b'x' | b'X', Length::Hh) => ArgKind::UChar,
+        (b'o' | b'u' | b'x' | b'X', Length::H) => ArgKind::UShort,
+        (b'o' | b'u' | b'x' | b'X', Length::None) => ArgKind::UInt,
+        (b'o' | b'u' | b'x' | b'X', Length::L) => ArgKind::ULong,
+        (b'o' | b'u' | b'x' | b'X', Length::Ll) => ArgKind::ULongLong,
+        (b'o' | b'u' | b'x' | b'X', Length::J) => ArgKind::UIntMax,
+        (b'o' | b'u' | b'x' | b'X', Length::Z) => ArgKind::Size,
+        (b'o' | b'u' | b'x' | b'X', Length::T) => ArgKind::PtrDiff,
+        (b'f' | b'F' | b'e' | b'E' | b'g' | b'G' | b'a' | b'A', Length::None | Length::L) => ArgKind::Double,
+        (b'f' | b'F' | b'e' | b'E' | b'g' | b'G' | b'a' | b'A', Length::BigL) => ArgKind::LongDouble,
+        (b'c', Length::None) => ArgKind::Int,
+        (b'c', Length::L) => ArgKind::WInt,
+        (b's', Length::None) => ArgKind::Str,
+        (b's', Length::L) => ArgKind::WideStr,
+        (b'p', Length::None) => ArgKind::Pointer,
+        // `%m` prints `strerror(errno)` in glibc's `printf()` and `syslog()`.
+        (b'm', Length::None) => return Ok(Directive { range: start..end, args }),
+        (b'n', _) => {
+            return Err(error(end, "`%n` writes to memory through a pointer argument and is not allowed"));
+        }
+        (b'%', _) => {
+            return Err(error(end, "`%%` must not have flags, a width, a precision, or a length modifier"));
+        }
+        (b'd' | b'i' | b'o' | b'u' | b'x' | b'X' | b'f' | b'F' | b'e' | b'E' |
+         b'g' | b'G' | b'a' | b'A' | b'c' | b's' | b'p' | b'm', _) => {
+            return Err(error(end, "invalid length modifier for conversion specifier"));
+        }
+        _ => return Err(error(end, "unknown conversion specifier")),
+    };
+
+    args.push(kind);
+
+    Ok(Directive { range: start..end, args })
+}
+
+/// The input of `printf_zstr!()`: either a bare literal, or an item
+/// declaring a unit struct that describes the format string.
+struct PrintfInput {
+    root: FfiRoot,
+    item: Option<(Vec<Attribute>, Visibility, Ident)>,
+    literal: Lit,
+}
+
+impl Parse for PrintfInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let root = input.parse()?;
+
+        if input.peek(Lit) {
+            let literal = input.parse()?;
+            return Ok(PrintfInput { root, item: None, literal });
+        }
+
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        input.parse::<Token![struct]>()?;
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let literal = input.parse()?;
+        input.parse::<Option<Token![;]>>()?;
+
+        Ok(PrintfInput { root, item: Some((attrs, vis, name)), literal })
+    }
+}
+
+/// Parses and validates the format string in `literal`, reporting errors
+/// at the offending directive where possible.
+pub fn validate_format(literal: &Lit) -> Result<(Vec<u8>, Vec<Directive>), Error> {
+    let bytes = literal_bytes(literal)?;
+
+    match parse_format(&bytes) {
+        Ok(directives) => Ok((bytes, directives)),
+        Err(error) => {
+            let span = literal_subspan(literal, error.range.clone());
+            let message = format!("{} (at index {})", error.message, error.range.start);
+            Err(Error::new(span, message))
+        }
+    }
+}
+
+/// Performs the actual expansion of `printf_zstr!()`.
+pub fn expand_printf_zstr(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let PrintfInput { root, item, literal } = PrintfInput::parse.parse2(input)?;
+    let span = literal.span();
+    let (bytes, directives) = validate_format(&literal)?;
+    let cstr = cstr_expr(&root, bytes, span);
+
+    let (attrs, vis, name) = match item {
+        Some(item) => item,
+        None => return Ok(cstr),
+    };
+
+    let ty = root.cstr(span);
+    let arg_types: Vec<_> = directives.iter().flat_map(|d| d.args.iter().map(|a| a.c_type())).collect();
+    let arg_count = arg_types.len();
+
+    Ok(quote_spanned!{
+        span =>
+        #(#attrs)*
+        #vis struct #name;
+
+        impl #name {
+            /// The validated format string.
+            #vis const FORMAT: &'static #ty = #cstr;
+            /// The number of arguments the format string consumes.
+            #vis const ARG_COUNT: usize = #arg_count;
+            /// The C types of the arguments the format string consumes.
+            #vis const ARG_TYPES: &'static [&'static str] = &[#(#arg_types),*];
+        }
+    })
+}
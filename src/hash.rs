@@ -0,0 +1,199 @@
+//! Hash functions evaluated over the bytes of string literals.
+
+use proc_macro2::{ Span, TokenStream as TokenStream2 };
+use syn::{ Error, Ident, LitInt, Token };
+use syn::parse::{ Parse, Parser, ParseStream };
+use quote::{ quote_spanned, ToTokens };
+use crate::ffi::FfiRoot;
+use crate::encoding::{ EncodingPrefix, encoded_literal_bytes };
+use crate::cstr_expr;
+
+/// A hash function supported by `zstr_hash!()`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Algorithm {
+    /// 32-bit FNV-1a.
+    Fnv1a32,
+    /// 64-bit FNV-1a.
+    Fnv1a64,
+    /// CRC-32 as used by zlib, PNG, and Ethernet (ISO-HDLC).
+    Crc32,
+    /// 32-bit xxHash with a seed of 0.
+    Xxh32,
+}
+
+impl Algorithm {
+    /// The names of the hash functions, as accepted by `zstr_hash!()`.
+    const NAMES: &'static [(&'static str, Algorithm)] = &[
+        ("fnv1a32", Algorithm::Fnv1a32),
+        ("fnv1a64", Algorithm::Fnv1a64),
+        ("crc32", Algorithm::Crc32),
+        ("xxh32", Algorithm::Xxh32),
+    ];
+
+    /// Hashes `bytes` and returns the hash as a suffixed integer literal.
+    fn hash(self, bytes: &[u8], span: Span) -> LitInt {
+        let literal = match self {
+            Algorithm::Fnv1a32 => format!("{:#010x}u32", fnv1a32(bytes)),
+            Algorithm::Fnv1a64 => format!("{:#018x}u64", fnv1a64(bytes)),
+            Algorithm::Crc32 => format!("{:#010x}u32", crc32(bytes)),
+            Algorithm::Xxh32 => format!("{:#010x}u32", xxh32(bytes)),
+        };
+
+        LitInt::new(&literal, span)
+    }
+}
+
+impl Parse for Algorithm {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident: Ident = input.parse()?;
+
+        Algorithm::NAMES
+            .iter()
+            .find(|&&(name, _)| ident == name)
+            .map(|&(_, algorithm)| algorithm)
+            .ok_or_else(|| {
+                let names: Vec<_> = Algorithm::NAMES.iter().map(|&(name, _)| name).collect();
+                let message = format!("unknown hash function `{}`; expected one of: {}", ident, names.join(", "));
+                Error::new(ident.span(), message)
+            })
+    }
+}
+
+/// The 32-bit FNV-1a hash of `bytes`.
+fn fnv1a32(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811C_9DC5, |hash, &byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// The 64-bit FNV-1a hash of `bytes`.
+pub fn fnv1a64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xCBF2_9CE4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01B3)
+    })
+}
+
+/// The CRC-32 of `bytes`, using the reflected polynomial `0xEDB88320`.
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, &byte| {
+        (0..8).fold(crc ^ u32::from(byte), |crc, _| {
+            (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+/// The 32-bit xxHash of `bytes`, with a seed of 0.
+fn xxh32(bytes: &[u8]) -> u32 {
+    const PRIME1: u32 = 0x9E37_79B1;
+    const PRIME2: u32 = 0x85EB_CA77;
+    const PRIME3: u32 = 0xC2B2_AE3D;
+    const PRIME4: u32 = 0x27D4_EB2F;
+    const PRIME5: u32 = 0x1656_67B1;
+
+    let read = |chunk: &[u8]| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    let round = |acc: u32, lane: u32| acc.wrapping_add(lane.wrapping_mul(PRIME2)).rotate_left(13).wrapping_mul(PRIME1);
+
+    let mut stripes = bytes.chunks_exact(16);
+    let mut hash = if bytes.len() >= 16 {
+        let mut acc = [PRIME1.wrapping_add(PRIME2), PRIME2, 0, PRIME1.wrapping_neg()];
+
+        for stripe in &mut stripes {
+            for (i, acc) in acc.iter_mut().enumerate() {
+                *acc = round(*acc, read(&stripe[i * 4..]));
+            }
+        }
+
+        acc[0].rotate_left(1)
+            .wrapping_add(acc[1].rotate_left(7))
+            .wrapping_add(acc[2].rotate_left(12))
+            .wrapping_add(acc[3].rotate_left(18))
+    } else {
+        PRIME5
+    };
+
+    hash = hash.wrapping_add(bytes.len() as u32);
+
+    let mut words = stripes.remainder().chunks_exact(4);
+
+    for word in &mut words {
+        hash = hash.wrapping_add(read(word).wrapping_mul(PRIME3)).rotate_left(17).wrapping_mul(PRIME4);
+    }
+
+    for &byte in words.remainder() {
+        hash = hash.wrapping_add(u32::from(byte).wrapping_mul(PRIME5)).rotate_left(11).wrapping_mul(PRIME1);
+    }
+
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(PRIME2);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(PRIME3);
+    hash ^ hash >> 16
+}
+
+/// The input of `zstr_hash!()` and `zstr_with_hash!()`.
+struct HashInput {
+    root: FfiRoot,
+    algorithm: Algorithm,
+    with_nul: bool,
+    bytes: Vec<u8>,
+    span: Span,
+}
+
+impl Parse for HashInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let root = input.parse()?;
+        let algorithm = input.parse()?;
+        input.parse::<Token![,]>()?;
+
+        let with_nul = input.peek(Ident) && input.peek2(Token![,]) && {
+            let ident: Ident = input.fork().parse()?;
+            ident == "with_nul"
+        };
+
+        if with_nul {
+            input.parse::<Ident>()?;
+            input.parse::<Token![,]>()?;
+        }
+
+        let prefix: EncodingPrefix = input.parse()?;
+        let literal = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        let bytes = encoded_literal_bytes(prefix.encoding, &literal)?;
+
+        Ok(HashInput { root, algorithm, with_nul, bytes, span: literal.span() })
+    }
+}
+
+impl HashInput {
+    /// Hashes the bytes of the literal, including the terminating 0
+    /// only if `with_nul` was specified.
+    fn hash(&self) -> LitInt {
+        let mut bytes = self.bytes.clone();
+
+        if self.with_nul {
+            bytes.push(0x00);
+        }
+
+        self.algorithm.hash(&bytes, self.span)
+    }
+}
+
+/// Performs the actual expansion of `zstr_hash!()`.
+pub fn expand_zstr_hash(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let input = HashInput::parse.parse2(input)?;
+
+    if let FfiRoot::Custom(path) = &input.root {
+        return Err(Error::new_spanned(path, "`zstr_hash!()` does not accept a `crate` argument"));
+    }
+
+    Ok(input.hash().into_token_stream())
+}
+
+/// Performs the actual expansion of `zstr_with_hash!()`.
+pub fn expand_zstr_with_hash(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let input = HashInput::parse.parse2(input)?;
+    let hash = input.hash();
+    let cstr = cstr_expr(&input.root, input.bytes, input.span);
+
+    Ok(quote_spanned!(input.span => (#cstr, #hash)))
+}
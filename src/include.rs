@@ -0,0 +1,56 @@
+//! Including files as C strings at compile time.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{ Error, LitStr };
+use syn::parse::{ Parser, ParseStream };
+use quote::quote_spanned;
+use crate::ffi::FfiRoot;
+use crate::{ ensure_no_nul, cstr_expr };
+
+/// Performs the actual expansion of `include_zstr!()`.
+pub fn expand_include_zstr(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let (root, literal) = Parser::parse2(
+        |input: ParseStream| Ok((input.parse::<FfiRoot>()?, input.parse::<LitStr>()?)),
+        input,
+    )?;
+    let span = literal.span();
+    let path = resolve_path(&literal)?;
+
+    let bytes = fs::read(&path).map_err(|error| {
+        Error::new(span, format!("couldn't read `{}`: {}", path.display(), error))
+    })?;
+
+    let what = format!("file `{}`", literal.value());
+    ensure_no_nul(&bytes, span, &what, "byte")?;
+
+    let cstr = cstr_expr(&root, bytes, span);
+    let tracked = LitStr::new(&path.to_string_lossy(), span);
+
+    Ok(quote_spanned!{
+        span => {
+            // Make the compiler aware of the dependency on the file,
+            // so that the crate is rebuilt when the file changes.
+            const _: &[u8] = include_bytes!(#tracked);
+            #cstr
+        }
+    })
+}
+
+/// Resolves the path in `literal` relative to the root directory of the
+/// invoking crate, i.e. the directory containing its `Cargo.toml`.
+fn resolve_path(literal: &LitStr) -> Result<PathBuf, Error> {
+    let path = PathBuf::from(literal.value());
+
+    if path.is_absolute() {
+        return Ok(path);
+    }
+
+    let dir = env::var_os("CARGO_MANIFEST_DIR").ok_or_else(|| {
+        Error::new(literal.span(), "`CARGO_MANIFEST_DIR` is not set; use an absolute path")
+    })?;
+
+    Ok(PathBuf::from(dir).join(path))
+}
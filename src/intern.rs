@@ -0,0 +1,108 @@
+//! Interned C strings, shared across crates through linker symbols.
+
+use proc_macro2::{ Span, TokenStream as TokenStream2 };
+use syn::{ Error, Ident, Lit, LitByteStr, Token };
+use syn::parse::{ Parse, Parser, ParseStream };
+use syn::punctuated::Punctuated;
+use quote::quote_spanned;
+use crate::ffi::FfiRoot;
+use crate::encoding::{ EncodingPrefix, encoded_literal_bytes };
+use crate::hash::fnv1a64;
+
+/// A string literal with an optional encoding prefix.
+struct InternedLiteral {
+    prefix: EncodingPrefix,
+    literal: Lit,
+}
+
+impl Parse for InternedLiteral {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(InternedLiteral {
+            prefix: input.parse()?,
+            literal: input.parse()?,
+        })
+    }
+}
+
+impl InternedLiteral {
+    /// Returns the bytes of the literal, including the terminating 0,
+    /// and the name of the symbol of the interned string.
+    fn bytes_and_symbol(&self) -> Result<(Vec<u8>, String), Error> {
+        let mut bytes = encoded_literal_bytes(self.prefix.encoding, &self.literal)?;
+        bytes.push(0x00);
+
+        // The length is part of the name, so that two different strings
+        // would have to collide in both length and hash to be confused.
+        let symbol = format!("__zstr_interned_{}_{:016x}", bytes.len(), fnv1a64(&bytes));
+
+        Ok((bytes, symbol))
+    }
+}
+
+/// Performs the actual expansion of `zstr_interned!()`.
+pub fn expand_zstr_interned(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let literals = Punctuated::<InternedLiteral, Token![,]>::parse_terminated.parse2(input)?;
+    let mut symbols: Vec<String> = Vec::with_capacity(literals.len());
+    let mut tokens = TokenStream2::new();
+
+    for literal in &literals {
+        let span = literal.literal.span();
+        let (bytes, symbol) = literal.bytes_and_symbol()?;
+
+        if symbols.contains(&symbol) {
+            return Err(Error::new(span, "duplicate interned string"));
+        }
+
+        let len = bytes.len();
+        let name = Ident::new(&symbol.to_uppercase(), span);
+        let bstr = LitByteStr::new(&bytes, span);
+        let export_name = if cfg!(zstr_no_unsafe_attributes) {
+            quote_spanned!(span => #[export_name = #symbol])
+        } else {
+            quote_spanned!(span => #[unsafe(export_name = #symbol)])
+        };
+
+        tokens.extend(quote_spanned!{
+            span =>
+            #export_name
+            static #name: [u8; #len] = *#bstr;
+        });
+        symbols.push(symbol);
+    }
+
+    Ok(quote_spanned!(Span::call_site() => const _: () = { #tokens };))
+}
+
+/// Performs the actual expansion of `zstr_intern!()`.
+pub fn expand_zstr_intern(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let (root, literal) = Parser::parse2(
+        |input: ParseStream| Ok((
+            input.parse::<FfiRoot>()?,
+            input.parse::<InternedLiteral>()?,
+        )),
+        input,
+    )?;
+    let span = literal.literal.span();
+    let (bytes, symbol) = literal.bytes_and_symbol()?;
+    let len = bytes.len();
+    let cstr = root.cstr(span);
+    let unsafe_extern = if cfg!(zstr_no_unsafe_attributes) {
+        quote_spanned!(span => extern "C")
+    } else {
+        quote_spanned!(span => unsafe extern "C")
+    };
+
+    Ok(quote_spanned!{
+        span => {
+            #unsafe_extern {
+                #[link_name = #symbol]
+                static INTERNED: [u8; #len];
+            }
+
+            // SAFETY: the name of the symbol is derived from the contents
+            // of the string, which is defined by `zstr_interned!()` to be
+            // NUL-terminated and not to contain any other, internal NULs.
+            unsafe { #cstr::from_bytes_with_nul_unchecked(&INTERNED) }
+        }
+    })
+}
@@ -0,0 +1,203 @@
+//! C string literals for the Java Native Interface, and validation of
+//! JNI type descriptors and class names.
+
+use std::ops::Range;
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{ Error, Lit };
+use syn::parse::{ Parser, ParseStream };
+use crate::ffi::FfiRoot;
+use crate::encoding::{ Encoding, encoded_literal_bytes };
+use crate::{ cstr_expr, literal_subspan };
+
+/// Performs the actual expansion of `jni_zstr!()`.
+pub fn expand_jni_zstr(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let (root, literal) = Parser::parse2(
+        |input: ParseStream| Ok((input.parse::<FfiRoot>()?, input.parse::<Lit>()?)),
+        input,
+    )?;
+    let span = literal.span();
+    let bytes = encoded_literal_bytes(Some((Encoding::Mutf8, span)), &literal)?;
+
+    Ok(cstr_expr(&root, bytes, span))
+}
+
+/// The kind of string validated by a JNI macro.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JniKind {
+    /// A field or method descriptor, as passed to `GetFieldID()` or
+    /// `GetMethodID()`.
+    Signature,
+    /// A class name in internal form, or an array descriptor, as passed
+    /// to `FindClass()`.
+    Class,
+}
+
+/// An error found in a JNI descriptor or class name.
+#[derive(Clone, Debug)]
+struct DescriptorError {
+    /// The byte range of the offending part of the string.
+    range: Range<usize>,
+    /// Describes what is wrong.
+    message: String,
+}
+
+/// A parser for the descriptor grammar of the JVM specification (§4.3).
+struct DescriptorParser<'a> {
+    /// The string being parsed.
+    text: &'a str,
+    /// The byte offset of the next character.
+    pos: usize,
+}
+
+impl<'a> DescriptorParser<'a> {
+    /// Returns the next character without consuming it.
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    /// Creates an error pointing at the next character (or at the end).
+    fn error(&self, message: &str) -> DescriptorError {
+        let len = self.peek().map_or(0, char::len_utf8);
+        DescriptorError {
+            range: self.pos..self.pos + len,
+            message: message.into(),
+        }
+    }
+
+    /// Ensures that the whole string has been consumed.
+    fn finish(&self) -> Result<(), DescriptorError> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error("unexpected trailing characters after descriptor")),
+        }
+    }
+
+    /// Parses a method descriptor, `( FieldType* ) ReturnType`.
+    fn method_descriptor(&mut self) -> Result<(), DescriptorError> {
+        self.pos += 1; // the opening `(`
+
+        loop {
+            match self.peek() {
+                Some(')') => break,
+                Some(_) => self.field_type()?,
+                None => return Err(self.error("unterminated parameter list; expected `)`")),
+            }
+        }
+
+        self.pos += 1; // the closing `)`
+
+        if self.peek() == Some('V') {
+            self.pos += 1;
+            Ok(())
+        } else {
+            self.field_type()
+        }
+    }
+
+    /// Parses a field type, i.e. a base type, an object type or an array type.
+    fn field_type(&mut self) -> Result<(), DescriptorError> {
+        let start = self.pos;
+        let mut dimensions = 0;
+
+        while self.peek() == Some('[') {
+            self.pos += 1;
+            dimensions += 1;
+        }
+
+        if dimensions > 255 {
+            return Err(DescriptorError {
+                range: start..self.pos,
+                message: "array type has more than 255 dimensions".into(),
+            });
+        }
+
+        match self.peek() {
+            Some('B' | 'C' | 'D' | 'F' | 'I' | 'J' | 'S' | 'Z') => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some('L') => {
+                self.pos += 1;
+                self.class_name(true)
+            }
+            Some('V') => Err(self.error("`V` (void) is only allowed as a return type")),
+            _ => Err(self.error("expected a field type: one of `B C D F I J S Z`, `L<class>;`, or `[`")),
+        }
+    }
+
+    /// Parses a class name in internal form, i.e. unqualified names separated
+    /// by `/`. If `in_descriptor` is `true`, the name must be terminated by `;`.
+    fn class_name(&mut self, in_descriptor: bool) -> Result<(), DescriptorError> {
+        let mut segment_start = self.pos;
+
+        loop {
+            match self.peek() {
+                Some(';') if in_descriptor => {
+                    if self.pos == segment_start {
+                        return Err(self.error("empty class name or package segment"));
+                    }
+                    self.pos += 1;
+                    return Ok(());
+                }
+                None if in_descriptor => {
+                    return Err(self.error("unterminated class name; expected `;`"));
+                }
+                None => {
+                    if self.pos == segment_start {
+                        return Err(self.error("empty class name or package segment"));
+                    }
+                    return Ok(());
+                }
+                Some('/') => {
+                    if self.pos == segment_start {
+                        return Err(self.error("empty class name or package segment"));
+                    }
+                    self.pos += 1;
+                    segment_start = self.pos;
+                }
+                Some(c @ ('.' | ';' | '[')) => {
+                    let message = format!("invalid character `{}` in class name", c);
+                    return Err(self.error(&message));
+                }
+                Some(c) => self.pos += c.len_utf8(),
+            }
+        }
+    }
+}
+
+/// Validates a JNI descriptor or class name according to `kind`.
+fn validate_jni(text: &str, kind: JniKind) -> Result<(), DescriptorError> {
+    let mut parser = DescriptorParser { text, pos: 0 };
+
+    match (kind, parser.peek()) {
+        (JniKind::Signature, Some('(')) => parser.method_descriptor()?,
+        (JniKind::Signature, _) | (JniKind::Class, Some('[')) => parser.field_type()?,
+        (JniKind::Class, _) => parser.class_name(false)?,
+    }
+
+    parser.finish()
+}
+
+/// Performs the actual expansion of `jni_sig!()` and `jni_class!()`.
+pub fn expand_jni_validated(input: TokenStream2, kind: JniKind) -> Result<TokenStream2, Error> {
+    let (root, literal) = Parser::parse2(
+        |input: ParseStream| Ok((input.parse::<FfiRoot>()?, input.parse::<Lit>()?)),
+        input,
+    )?;
+    let span = literal.span();
+
+    let text = match &literal {
+        Lit::Str(lit) => lit.value(),
+        _ => return Err(Error::new(span, "expected a string literal")),
+    };
+
+    if let Err(error) = validate_jni(&text, kind) {
+        let span = literal_subspan(&literal, error.range.clone());
+        let message = format!("{} (at index {})", error.message, error.range.start);
+        return Err(Error::new(span, message));
+    }
+
+    let bytes = encoded_literal_bytes(Some((Encoding::Mutf8, span)), &literal)?;
+
+    Ok(cstr_expr(&root, bytes, span))
+}
@@ -1,9 +1,48 @@
 //! Zero-terminated C string literals.
+//!
+//! The generated code refers to `core::ffi::CStr`, so these macros can
+//! be used from `#![no_std]` crates as well. On toolchains older than
+//! Rust 1.64, where `CStr` is not yet available in `core`, the paths
+//! fall back to `std` automatically. If neither is desired (e.g. because
+//! `core` is renamed, or a facade crate should be used), the crate in
+//! which the `ffi` module is looked up can be overridden by passing a
+//! leading `crate = path,` argument:
+//!
+//! ```
+//! use zstr::zstr;
+//!
+//! let c_str = zstr!(crate = ::std, "from std::ffi");
+//! assert_eq!(c_str.to_bytes(), b"from std::ffi");
+//! ```
 
+use std::ops::Range;
 use proc_macro::TokenStream;
-use proc_macro2::TokenStream as TokenStream2;
-use syn::{ parse2, Error, Lit, LitByteStr };
-use quote::quote_spanned;
+use proc_macro2::{ Span, TokenStream as TokenStream2 };
+use syn::{ parse2, parse_macro_input, DeriveInput, Error, Lit, LitByteStr, LitInt, Macro };
+use syn::parse::{ Parser, ParseStream };
+use quote::{ quote_spanned, ToTokens };
+use ffi::FfiRoot;
+use encoding::{ EncodingPrefix, encoded_literal_bytes };
+
+mod ffi;
+mod wide;
+mod concat;
+mod nested;
+mod include;
+mod env;
+mod array;
+mod multi;
+mod format;
+mod zprintf;
+mod encoding;
+mod cjk;
+mod jni;
+mod statics;
+mod fixed;
+mod derive;
+mod auto;
+mod hash;
+mod intern;
 
 /// Given a Rust string or byte string literal, this macro
 /// generates an expression of type `&'static CStr` that is
@@ -39,6 +78,102 @@ use quote::quote_spanned;
 /// let invalid_3 = zstr!(b"and in byte \x00 strings too");
 /// let invalid_4 = zstr!(b"at the end of byte strings: \0");
 /// ```
+///
+/// Instead of a literal, the input may also be an invocation of one of
+/// the built-in macros `concat!`, `stringify!`, `env!`, `option_env!`,
+/// `include_str!` or `include_bytes!`. Since their output is not known
+/// to `zstr!()`, the absence of embedded NUL bytes is then checked
+/// during constant evaluation instead. `option_env!` results in an
+/// `Option<&'static CStr>`:
+///
+/// ```
+/// # use zstr::zstr;
+/// #
+/// let c_str_4 = zstr!(concat!("Hello", ' ', "World ", 42));
+/// assert_eq!(c_str_4.to_bytes(), b"Hello World 42");
+///
+/// let c_str_5 = zstr!(stringify!(hello));
+/// assert_eq!(c_str_5.to_bytes(), b"hello");
+///
+/// let c_str_6 = zstr!(env!("CARGO_PKG_NAME"));
+/// assert_eq!(c_str_6.to_bytes(), b"zstr");
+///
+/// let c_str_7 = zstr!(option_env!("ZSTR_SURELY_NOT_DEFINED"));
+/// assert_eq!(c_str_7, None);
+///
+/// const C_STR_8: &std::ffi::CStr = zstr!(include_bytes!("../LICENSE.txt"));
+/// assert!(C_STR_8.to_bytes().starts_with(b"MIT License"));
+/// ```
+///
+/// Embedded NUL bytes are still rejected, but only by a (less specific)
+/// constant evaluation error:
+///
+/// ```compile_fail
+/// # use zstr::zstr;
+/// #
+/// let invalid = zstr!(concat!("null ", '\0', " here"));
+/// ```
+///
+/// By default, the bytes of the C string are the UTF-8 encoding of the
+/// string literal. The string can be transcoded to a legacy encoding at
+/// compile time instead, by prefixing it with the name of the encoding
+/// and a colon. The supported encodings are `utf8` (the default), `ascii`,
+/// `latin1` (ISO-8859-1), `cp1252` (Windows-1252), as well as the CJK
+/// multi-byte encodings `shift_jis`, `gbk`, `gb18030` and `euc_kr`, and
+/// `mutf8` (modified UTF-8):
+///
+/// ```
+/// # use zstr::zstr;
+/// #
+/// assert_eq!(zstr!(latin1: "café").to_bytes(), b"caf\xe9");
+/// assert_eq!(zstr!(cp1252: "5 €").to_bytes(), b"5 \x80");
+/// assert_eq!(zstr!(ascii: "HTTP/1.1").to_bytes(), b"HTTP/1.1");
+/// assert_eq!(zstr!(utf8: "café").to_bytes(), "café".as_bytes());
+///
+/// assert_eq!(zstr!(shift_jis: "日本語 ｶﾀｶﾅ").to_bytes(), b"\x93\xfa\x96{\x8c\xea \xb6\xc0\xb6\xc5");
+/// assert_eq!(zstr!(gbk: "中文").to_bytes(), b"\xd6\xd0\xce\xc4");
+/// assert_eq!(zstr!(gb18030: "€ 😀").to_bytes(), b"\xa2\xe3 \x94\x39\xfc\x36");
+/// assert_eq!(zstr!(euc_kr: "한국어").to_bytes(), b"\xc7\xd1\xb1\xb9\xbe\xee");
+/// ```
+///
+/// The `mutf8` encoding produces Java's modified UTF-8, which is also
+/// available as `jni_zstr!()`. Since it encodes U+0000 as `C0 80`,
+/// embedded NUL characters are allowed in this encoding.
+///
+/// Characters that cannot be represented in the chosen encoding are
+/// rejected:
+///
+/// ```compile_fail
+/// # use zstr::zstr;
+/// #
+/// let invalid = zstr!(ascii: "café");
+/// ```
+///
+/// ```compile_fail
+/// # use zstr::zstr;
+/// #
+/// let invalid = zstr!(latin1: "5 €");
+/// ```
+///
+/// ```compile_fail
+/// # use zstr::zstr;
+/// #
+/// let invalid = zstr!(shift_jis: "한국어");
+/// ```
+///
+/// The path to `CStr` can be overridden using a leading `crate = path,`
+/// argument. The type is then looked up as `path::ffi::CStr`:
+///
+/// ```
+/// # use zstr::zstr;
+/// #
+/// mod facade {
+///     pub use std::ffi;
+/// }
+///
+/// const C_STR: &std::ffi::CStr = zstr!(crate = facade, "via a facade");
+/// assert_eq!(C_STR.to_bytes(), b"via a facade");
+/// ```
 #[proc_macro]
 pub fn zstr(input: TokenStream) -> TokenStream {
     expand_zstr(input.into())
@@ -48,10 +183,41 @@ pub fn zstr(input: TokenStream) -> TokenStream {
 
 /// Performs the actual expansion of `zstr!()`.
 fn expand_zstr(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let (root, prefix, input) = Parser::parse2(
+        |input: ParseStream| Ok((
+            input.parse::<FfiRoot>()?,
+            input.parse::<EncodingPrefix>()?,
+            input.parse::<TokenStream2>()?,
+        )),
+        input,
+    )?;
+
+    if let Ok(mac) = parse2::<Macro>(input.clone()) {
+        if let Some((_, span)) = prefix.encoding {
+            return Err(Error::new(span, "encodings can only be applied to string literals"));
+        }
+        return nested::expand_nested(&root, mac);
+    }
+
     let literal: Lit = parse2(input)?;
+    let bytes = encoded_literal_bytes(prefix.encoding, &literal)?;
+
+    Ok(cstr_expr(&root, bytes, literal.span()))
+}
+
+/// Expands a string or byte string literal to an expression of type
+/// `&'static CStr`, ensuring that it does not contain NUL bytes.
+fn literal_cstr(root: &FfiRoot, literal: &Lit) -> Result<TokenStream2, Error> {
+    let bytes = literal_bytes(literal)?;
+    Ok(cstr_expr(root, bytes, literal.span()))
+}
+
+/// Returns the bytes of a string or byte string literal, without the
+/// terminating 0, ensuring that it does not contain NUL bytes.
+fn literal_bytes(literal: &Lit) -> Result<Vec<u8>, Error> {
     let span = literal.span();
 
-    let mut bytes = match literal {
+    let bytes = match literal {
         Lit::Str(lit) => lit.value().into_bytes(),
         Lit::ByteStr(lit) => lit.value(),
         _ => return Err(Error::new(span, "expected a string or byte string literal")),
@@ -59,24 +225,1232 @@ fn expand_zstr(input: TokenStream2) -> Result<TokenStream2, Error> {
 
     // Ensure that no 0 byte is in the string literal, as that
     // would cause inconsistencies in the length of the string.
-    if let Some(index) = bytes.iter().position(|&b| b == 0x00) {
-        let message = format!("C string contains an embedded NUL byte at index {}", index);
-        return Err(Error::new(span, message));
-    }
+    ensure_no_nul(&bytes, span, "C string", "byte")?;
+
+    Ok(bytes)
+}
 
+/// Appends the terminating 0 to `bytes`, then expands to an expression
+/// of type `&'static CStr`. The caller must already have ensured that
+/// `bytes` does not contain any NUL bytes.
+fn cstr_expr(root: &FfiRoot, mut bytes: Vec<u8>, span: Span) -> TokenStream2 {
     // Add the terminating 0.
     bytes.reserve_exact(1);
     bytes.push(0x00);
 
     // Convert to a byte string literal.
     let bstr = LitByteStr::new(&bytes, span);
+    let cstr = root.cstr(span);
 
     // Expand to an expression of type `&'static CStr`.
-    Ok(quote_spanned!{
+    quote_spanned!{
         // SAFETY: the input is NUL-terminated and it is ensured
         // that it does not contain any other, internal NUL bytes.
         span => unsafe {
-            ::std::ffi::CStr::from_bytes_with_nul_unchecked(#bstr)
+            #cstr::from_bytes_with_nul_unchecked(#bstr)
         }
-    })
+    }
+}
+
+/// Given a Rust string literal, this macro generates an expression
+/// of type `&'static [u16]` that contains the UTF-16 encoding of the
+/// string, followed by a single terminating `0u16`, as expected by
+/// wide-character (e.g. Windows `*W`) APIs. The string must not
+/// contain any embedded NUL (U+0000) characters. The resulting
+/// expression can be used in `const` context.
+///
+/// ### Examples:
+///
+/// ```
+/// use zstr::wzstr;
+///
+/// const WIDE: &[u16] = wzstr!("Hi 🎉");
+/// assert_eq!(WIDE, &[0x0048, 0x0069, 0x0020, 0xD83C, 0xDF89, 0x0000]);
+///
+/// let expected: Vec<u16> = "Grüße".encode_utf16().chain(Some(0)).collect();
+/// assert_eq!(wzstr!("Grüße"), expected.as_slice());
+/// ```
+///
+/// Embedded NUL characters and byte string literals are not allowed:
+///
+/// ```compile_fail
+/// # use zstr::wzstr;
+/// #
+/// let invalid = wzstr!("null here: \0 is forbidden");
+/// ```
+///
+/// ```compile_fail
+/// # use zstr::wzstr;
+/// #
+/// let invalid = wzstr!(b"not UTF-16");
+/// ```
+#[proc_macro]
+pub fn wzstr(input: TokenStream) -> TokenStream {
+    wide::expand_wzstr(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Given a Rust string literal, this macro generates an expression
+/// of type `&'static [u32]` that contains the UTF-32 encoding of the
+/// string (i.e. its Unicode scalar values), followed by a single
+/// terminating `0u32`. This is the representation of wide strings
+/// on platforms where `wchar_t` is 32 bits wide, e.g. Linux. The
+/// string must not contain any embedded NUL (U+0000) characters.
+/// The resulting expression can be used in `const` context.
+///
+/// If `wchar_t` is signed on the target (as `libc::wchar_t` is on
+/// Linux), the pointer can simply be cast: `WIDE.as_ptr().cast()`.
+///
+/// ### Examples:
+///
+/// ```
+/// use zstr::wzstr32;
+///
+/// const WIDE: &[u32] = wzstr32!("Hi 🎉");
+/// assert_eq!(WIDE, &[0x48, 0x69, 0x20, 0x1F389, 0x00]);
+///
+/// let expected: Vec<u32> = "Grüße".chars().map(u32::from).chain(Some(0)).collect();
+/// assert_eq!(wzstr32!("Grüße"), expected.as_slice());
+/// ```
+///
+/// Embedded NUL characters are not allowed:
+///
+/// ```compile_fail
+/// # use zstr::wzstr32;
+/// #
+/// let invalid = wzstr32!("at the end: \0");
+/// ```
+#[proc_macro]
+pub fn wzstr32(input: TokenStream) -> TokenStream {
+    wide::expand_wzstr32(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Concatenates string, byte string, character, byte, integer,
+/// floating-point and boolean literals into a single `&'static CStr`,
+/// much like `core::concat!()` does for `&'static str`. The combined
+/// bytes are 0-terminated and ensured not to contain any internal
+/// NUL bytes. The resulting expression can be used in `const` context.
+///
+/// Integers and floating-point numbers are written in decimal without
+/// their type suffix, and booleans as `true` or `false`. Strings and
+/// characters are UTF-8-encoded, while byte strings and bytes are
+/// copied verbatim.
+///
+/// ### Examples:
+///
+/// ```
+/// use zstr::zstr_concat;
+///
+/// let soname = zstr_concat!("lib", "foo", ".so.", 1);
+/// assert_eq!(soname.to_bytes_with_nul(), b"libfoo.so.1\0");
+///
+/// let mixed = zstr_concat!(b"bytes ", 'c', b'!', ' ', 0x10u8, " ", 2.5, " ", false);
+/// assert_eq!(mixed.to_bytes(), b"bytes c! 16 2.5 false");
+///
+/// assert_eq!(zstr_concat!().to_bytes(), b"");
+/// ```
+///
+/// An embedded NUL byte in any of the arguments is an error, which is
+/// reported at the offending argument:
+///
+/// ```compile_fail
+/// # use zstr::zstr_concat;
+/// #
+/// let invalid = zstr_concat!("fine", "not \0 fine");
+/// ```
+#[proc_macro]
+pub fn zstr_concat(input: TokenStream) -> TokenStream {
+    concat::expand_zstr_concat(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Reads a file at compile time, and generates an expression of type
+/// `&'static CStr` from its contents, which is properly 0-terminated and
+/// ensured not to contain any internal NUL (0) bytes. The resulting
+/// expression can be used in `const` context.
+///
+/// Unlike `include_str!()`, relative paths are resolved against the root
+/// directory of the invoking crate (the one containing its `Cargo.toml`),
+/// not against the directory of the invoking source file. The file is
+/// tracked by the compiler, so the crate is rebuilt when it changes.
+///
+/// Like `zstr!()`, this macro accepts a leading `crate = path,` argument.
+///
+/// ### Examples:
+///
+/// ```
+/// use zstr::include_zstr;
+///
+/// const LICENSE: &std::ffi::CStr = include_zstr!("LICENSE.txt");
+/// assert!(LICENSE.to_bytes().starts_with(b"MIT License"));
+/// assert_eq!(LICENSE.to_bytes(), include_bytes!("../LICENSE.txt"));
+/// ```
+///
+/// Files containing NUL bytes are rejected, and the error message
+/// includes the offset of the first NUL byte:
+///
+/// ```compile_fail
+/// # use zstr::include_zstr;
+/// #
+/// let invalid = include_zstr!("tests/fixtures/embedded_nul.txt");
+/// ```
+///
+/// ```compile_fail
+/// # use zstr::include_zstr;
+/// #
+/// let missing = include_zstr!("surely/does/not/exist.txt");
+/// ```
+#[proc_macro]
+pub fn include_zstr(input: TokenStream) -> TokenStream {
+    include::expand_include_zstr(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Reads an environment variable at compile time, and generates an
+/// expression of type `&'static CStr` from its value, which is properly
+/// 0-terminated and ensured not to contain any internal NUL (0) bytes.
+/// The resulting expression can be used in `const` context.
+///
+/// Like `env!()`, this macro fails to compile if the variable is not
+/// defined, and accepts an optional second argument which replaces the
+/// default error message in that case. It also accepts a leading
+/// `crate = path,` argument like `zstr!()`.
+///
+/// ### Examples:
+///
+/// ```
+/// use zstr::env_zstr;
+///
+/// const NAME: &std::ffi::CStr = env_zstr!("CARGO_PKG_NAME");
+/// assert_eq!(NAME.to_bytes(), b"zstr");
+/// assert_eq!(NAME.to_bytes(), env!("CARGO_PKG_NAME").as_bytes());
+/// ```
+///
+/// ```compile_fail
+/// # use zstr::env_zstr;
+/// #
+/// let missing = env_zstr!("ZSTR_SURELY_NOT_DEFINED", "please define it");
+/// ```
+#[proc_macro]
+pub fn env_zstr(input: TokenStream) -> TokenStream {
+    env::expand_env_zstr(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Like `env_zstr!()`, but generates an expression of type
+/// `Option<&'static CStr>`, which is `None` if the environment variable
+/// is not defined at compile time, mirroring `option_env!()`.
+///
+/// ### Examples:
+///
+/// ```
+/// use zstr::option_env_zstr;
+///
+/// let version = option_env_zstr!("CARGO_PKG_VERSION").unwrap();
+/// assert_eq!(version.to_bytes(), env!("CARGO_PKG_VERSION").as_bytes());
+///
+/// assert_eq!(option_env_zstr!("ZSTR_SURELY_NOT_DEFINED"), None);
+/// ```
+#[proc_macro]
+pub fn option_env_zstr(input: TokenStream) -> TokenStream {
+    env::expand_option_env_zstr(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Given a list of Rust string or byte string literals, this macro
+/// generates an expression of type `&'static [*const c_char]`, which
+/// contains pointers to the 0-terminated strings, followed by a null
+/// pointer. This is the format of e.g. the `argv` and `envp` arguments
+/// of `execve()`. Each string is handled exactly like by `zstr!()`.
+/// The resulting expression can be used in `const` context.
+///
+/// Since raw pointers are not `Sync`, the result can be stored in a
+/// `const`, but not directly in a `static`.
+///
+/// Like `zstr!()`, this macro accepts a leading `crate = path,` argument.
+///
+/// ### Examples:
+///
+/// ```
+/// use std::ffi::CStr;
+/// use std::os::raw::c_char;
+/// use zstr::zstr_array;
+///
+/// const ARGV: &[*const c_char] = zstr_array!["ls", "-l", b"/tmp"];
+/// assert_eq!(ARGV.len(), 4);
+/// assert!(ARGV[3].is_null());
+///
+/// let args: Vec<&[u8]> = ARGV
+///     .iter()
+///     .take_while(|ptr| !ptr.is_null())
+///     .map(|&ptr| unsafe { CStr::from_ptr(ptr) }.to_bytes())
+///     .collect();
+/// assert_eq!(args, [&b"ls"[..], b"-l", b"/tmp"]);
+///
+/// let empty = zstr_array![];
+/// assert_eq!(empty, [std::ptr::null()]);
+/// ```
+///
+/// Strings with embedded NUL (zero) bytes are not allowed:
+///
+/// ```compile_fail
+/// # use zstr::zstr_array;
+/// #
+/// let invalid = zstr_array!["fine", "not \0 fine"];
+/// ```
+#[proc_macro]
+pub fn zstr_array(input: TokenStream) -> TokenStream {
+    array::expand_zstr_array(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Given a list of Rust string or byte string literals, this macro
+/// generates an expression of type `&'static [u8]`, which contains each
+/// string followed by a 0 byte, and an additional terminating 0 byte
+/// after the last one. This is the format of e.g. Windows `REG_MULTI_SZ`
+/// values and environment blocks. The resulting expression can be used
+/// in `const` context.
+///
+/// Since an empty string would be indistinguishable from the end of the
+/// list, none of the strings may be empty or contain NUL bytes. An empty
+/// list is represented by a single 0 byte.
+///
+/// The companion `zstr-list` crate provides a `ZStrList` type for
+/// validating and iterating over such lists, which can be created
+/// from literals using its `zstr_list!()` macro.
+///
+/// ### Examples:
+///
+/// ```
+/// use zstr::zstr_multi;
+///
+/// const MULTI: &[u8] = zstr_multi!("a", "bc", b"def");
+/// assert_eq!(MULTI, b"a\0bc\0def\0\0");
+///
+/// assert_eq!(zstr_multi!(), b"\0");
+/// ```
+///
+/// ```compile_fail
+/// # use zstr::zstr_multi;
+/// #
+/// let invalid = zstr_multi!("fine", "", "would be truncated");
+/// ```
+///
+/// ```compile_fail
+/// # use zstr::zstr_multi;
+/// #
+/// let invalid = zstr_multi!("fine", "not \0 fine");
+/// ```
+#[proc_macro]
+pub fn zstr_multi(input: TokenStream) -> TokenStream {
+    multi::expand_zstr_multi(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Like `zstr!()`, but additionally parses the literal as a C `printf()`
+/// format string at compile time, and rejects invalid conversion
+/// specifications, as well as the dangerous `%n` specifier.
+///
+/// Flags, field widths and precisions (including `*`), all standard
+/// length modifiers, and the conversion specifiers `d i o u x X f F e E
+/// g G a A c s p %` are supported, as is `m`, which prints
+/// `strerror(errno)` in glibc's `printf()` and `syslog()`, and consumes no
+/// argument. Positional arguments (`%1$d`) are not supported.
+///
+/// ### Examples:
+///
+/// ```
+/// use zstr::printf_zstr;
+///
+/// let format = printf_zstr!("%s: %-8d|%5.2f|%llx|%%\n");
+/// assert_eq!(format.to_bytes(), b"%s: %-8d|%5.2f|%llx|%%\n");
+/// ```
+///
+/// Instead of a bare literal, an item declaring a unit struct can also
+/// be passed. The struct then exposes the format string as well as the
+/// number and C types of the arguments it consumes as associated consts:
+///
+/// ```
+/// # use zstr::printf_zstr;
+/// #
+/// printf_zstr!(pub struct Greeting = "%s is %*d years old\n");
+///
+/// assert_eq!(Greeting::FORMAT.to_bytes(), b"%s is %*d years old\n");
+/// assert_eq!(Greeting::ARG_COUNT, 3);
+/// assert_eq!(Greeting::ARG_TYPES, ["const char *", "int", "int"]);
+/// ```
+///
+/// This includes `syslog()` format strings using `%m`:
+///
+/// ```
+/// # use zstr::printf_zstr;
+/// #
+/// printf_zstr!(struct OpenFailed = "cannot open %s: %m");
+///
+/// assert_eq!(OpenFailed::ARG_COUNT, 1);
+/// assert_eq!(OpenFailed::ARG_TYPES, ["const char *"]);
+/// ```
+///
+/// Invalid and dangerous directives are rejected:
+///
+/// ```compile_fail
+/// # use zstr::printf_zstr;
+/// #
+/// let invalid = printf_zstr!("%d%n");
+/// ```
+///
+/// ```compile_fail
+/// # use zstr::printf_zstr;
+/// #
+/// let invalid = printf_zstr!("%hs");
+/// ```
+///
+/// ```compile_fail
+/// # use zstr::printf_zstr;
+/// #
+/// let invalid = printf_zstr!("100%");
+/// ```
+#[proc_macro]
+pub fn printf_zstr(input: TokenStream) -> TokenStream {
+    format::expand_printf_zstr(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Calls a variadic C formatting function (e.g. `printf()`) with a format
+/// string validated by `printf_zstr!()`, and checks that the Rust type
+/// of each argument matches the corresponding conversion specification.
+///
+/// The first argument is the function to call, the second one is the
+/// format string literal, and the rest are the formatting arguments.
+/// If the function is given as a call expression, e.g. `snprintf(buf, len)`,
+/// then its arguments are passed before the format string.
+///
+/// Integer and floating-point arguments must have exactly the C type
+/// required by the specification, e.g. `c_int` for `%d`, `c_long` for `%ld`,
+/// `c_ulonglong` for `%llu`, `usize` for `%zu`, and `c_double` for `%f`.
+/// Arguments of `%hhd` and `%hd` etc. must be `c_schar` and `c_short`,
+/// respectively, and they are promoted to `c_int` automatically. The
+/// `intmax_t` and `uintmax_t` types (`%jd` and `%ju`) are assumed to be
+/// 64 bits wide. Arguments of `%s` may be `&CStr` or pointers to `c_char`,
+/// and arguments of `%p` may be any raw pointers to sized types. Wide
+/// characters and strings, as well as `long double`, are not supported.
+///
+/// The call itself is not wrapped in an `unsafe` block, since the macro
+/// can't check that the function is sound to call, or that the pointer
+/// arguments are valid.
+///
+/// ### Examples:
+///
+/// ```
+/// # #![deny(unused_unsafe)]
+/// use std::ffi::CStr;
+/// use std::os::raw::{ c_char, c_int, c_long };
+/// use zstr::{ zprintf, zstr };
+///
+/// extern "C" {
+///     fn snprintf(buf: *mut c_char, size: usize, format: *const c_char, ...) -> c_int;
+/// }
+///
+/// let mut buf = [0 as c_char; 64];
+/// let name = zstr!("answer");
+/// let value: c_long = -42;
+///
+/// let len = unsafe {
+///     zprintf!(snprintf(buf.as_mut_ptr(), buf.len()), "%s=%ld (%#x) %.1f%%", name, value, 255, 99.5)
+/// };
+/// let result = unsafe { CStr::from_ptr(buf.as_ptr()) };
+///
+/// assert_eq!(result.to_bytes(), b"answer=-42 (0xff) 99.5%");
+/// assert_eq!(len as usize, result.to_bytes().len());
+/// ```
+///
+/// The arguments are evaluated in the scope of the caller, so they can
+/// not accidentally refer to items generated by the macro:
+///
+/// ```
+/// use std::ffi::CStr;
+/// use std::os::raw::{ c_char, c_int };
+/// use zstr::zprintf;
+///
+/// extern "C" {
+///     fn snprintf(buf: *mut c_char, size: usize, format: *const c_char, ...) -> c_int;
+/// }
+///
+/// const FORMAT: c_int = 5;
+/// let mut buf = [0 as c_char; 16];
+///
+/// unsafe { zprintf!(snprintf(buf.as_mut_ptr(), buf.len()), "%d", FORMAT) };
+///
+/// assert_eq!(unsafe { CStr::from_ptr(buf.as_ptr()) }.to_bytes(), b"5");
+/// ```
+///
+/// Mismatched argument types are rejected:
+///
+/// ```compile_fail
+/// # use std::os::raw::{ c_char, c_int };
+/// # use zstr::zprintf;
+/// #
+/// # extern "C" {
+/// #     fn printf(format: *const c_char, ...) -> c_int;
+/// # }
+/// #
+/// let value: i64 = 1;
+/// unsafe { zprintf!(printf, "%d\n", value) };
+/// ```
+///
+/// ```compile_fail
+/// # use std::os::raw::{ c_char, c_int };
+/// # use zstr::zprintf;
+/// #
+/// # extern "C" {
+/// #     fn printf(format: *const c_char, ...) -> c_int;
+/// # }
+/// #
+/// unsafe { zprintf!(printf, "%s\n", "not a C string") };
+/// ```
+///
+/// So are mismatched argument counts:
+///
+/// ```compile_fail
+/// # use std::os::raw::{ c_char, c_int };
+/// # use zstr::zprintf;
+/// #
+/// # extern "C" {
+/// #     fn printf(format: *const c_char, ...) -> c_int;
+/// # }
+/// #
+/// unsafe { zprintf!(printf, "%d %d\n", 1) };
+/// ```
+#[proc_macro]
+pub fn zprintf(input: TokenStream) -> TokenStream {
+    zprintf::expand_zprintf(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Given a Rust string literal, this macro generates an expression of
+/// type `&'static CStr` that contains the string in Java's "modified
+/// UTF-8" encoding, as expected by JNI functions such as `FindClass()`
+/// and `GetMethodID()`. It is equivalent to `zstr!(mutf8: "...")`.
+/// The resulting expression can be used in `const` context.
+///
+/// Modified UTF-8 differs from UTF-8 in two ways: U+0000 is encoded as
+/// the two bytes `C0 80`, so embedded NUL characters are allowed; and
+/// supplementary characters are encoded as UTF-16 surrogate pairs, each
+/// half taking 3 bytes.
+///
+/// Like `zstr!()`, this macro accepts a leading `crate = path,` argument.
+///
+/// ### Examples:
+///
+/// ```
+/// use zstr::jni_zstr;
+///
+/// let sig = jni_zstr!("(Ljava/lang/String;)V");
+/// assert_eq!(sig.to_bytes(), b"(Ljava/lang/String;)V");
+///
+/// let nul = jni_zstr!("a\0b");
+/// assert_eq!(nul.to_bytes(), b"a\xc0\x80b");
+///
+/// let emoji = jni_zstr!("é🎉");
+/// assert_eq!(emoji.to_bytes(), b"\xc3\xa9\xed\xa0\xbc\xed\xbe\x89");
+/// ```
+///
+/// Byte string literals are not allowed:
+///
+/// ```compile_fail
+/// # use zstr::jni_zstr;
+/// #
+/// let invalid = jni_zstr!(b"java/lang/Object");
+/// ```
+#[proc_macro]
+pub fn jni_zstr(input: TokenStream) -> TokenStream {
+    jni::expand_jni_zstr(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Like `jni_zstr!()`, but additionally validates the literal as a JNI
+/// type signature (a field or method descriptor, as specified by §4.3 of
+/// the JVM specification) at compile time. The result can be passed to
+/// e.g. `GetMethodID()` or `GetStaticFieldID()`.
+///
+/// ### Examples:
+///
+/// ```
+/// use zstr::jni_sig;
+///
+/// let method = jni_sig!("(ILjava/lang/String;[[D)V");
+/// assert_eq!(method.to_bytes(), b"(ILjava/lang/String;[[D)V");
+///
+/// let field = jni_sig!("[Ljava/util/Map$Entry;");
+/// assert_eq!(field.to_bytes(), b"[Ljava/util/Map$Entry;");
+/// ```
+///
+/// Malformed descriptors are rejected:
+///
+/// ```compile_fail
+/// # use zstr::jni_sig;
+/// #
+/// let invalid = jni_sig!("(Ljava/lang/String)V"); // missing `;`
+/// ```
+///
+/// ```compile_fail
+/// # use zstr::jni_sig;
+/// #
+/// let invalid = jni_sig!("(Ljava.lang.String;)V"); // `.` instead of `/`
+/// ```
+///
+/// ```compile_fail
+/// # use zstr::jni_sig;
+/// #
+/// let invalid = jni_sig!("(V)I"); // `void` parameter
+/// ```
+#[proc_macro]
+pub fn jni_sig(input: TokenStream) -> TokenStream {
+    jni::expand_jni_validated(input.into(), jni::JniKind::Signature)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Like `jni_zstr!()`, but additionally validates the literal as a class
+/// name in internal form (e.g. `java/lang/String`) or as an array type
+/// descriptor (e.g. `[Ljava/lang/String;`), as accepted by `FindClass()`.
+///
+/// ### Examples:
+///
+/// ```
+/// use zstr::jni_class;
+///
+/// assert_eq!(jni_class!("java/lang/String").to_bytes(), b"java/lang/String");
+/// assert_eq!(jni_class!("[[I").to_bytes(), b"[[I");
+/// ```
+///
+/// ```compile_fail
+/// # use zstr::jni_class;
+/// #
+/// let invalid = jni_class!("java.lang.String");
+/// ```
+///
+/// ```compile_fail
+/// # use zstr::jni_class;
+/// #
+/// let invalid = jni_class!("java//String");
+/// ```
+#[proc_macro]
+pub fn jni_class(input: TokenStream) -> TokenStream {
+    jni::expand_jni_validated(input.into(), jni::JniKind::Class)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Declares one or more `static` items containing 0-terminated C strings,
+/// e.g. for exporting them to C code that looks them up by symbol name.
+///
+/// Each item is written like a `static` declaration without a type, and
+/// its value is a string literal as accepted by `zstr!()` (including an
+/// optional encoding prefix). The type of the static is `[u8; N]`, where
+/// `N` includes the terminating 0 byte, which corresponds to a `const char
+/// name[N]` array in C. Attributes like `#[no_mangle]`, `#[used]` and
+/// `#[link_section]` are passed through.
+///
+/// ### Examples:
+///
+/// ```
+/// use std::ffi::CStr;
+/// use zstr::zstr_static;
+///
+/// zstr_static! {
+///     /// The name of the plugin.
+///     #[no_mangle]
+///     pub static PLUGIN_NAME = "foo";
+///
+///     #[used]
+///     #[cfg_attr(target_os = "linux", link_section = ".rodata.plugin_version")]
+///     static PLUGIN_VERSION = latin1: "1.0 café";
+/// }
+///
+/// assert_eq!(&PLUGIN_NAME, b"foo\0");
+/// assert_eq!(&PLUGIN_VERSION, b"1.0 caf\xe9\0");
+///
+/// let name = CStr::from_bytes_with_nul(&PLUGIN_NAME).unwrap();
+/// assert_eq!(name.to_bytes(), b"foo");
+/// ```
+///
+/// The semicolon after the last item may be omitted, which is convenient
+/// for declaring a single item:
+///
+/// ```
+/// # use zstr::zstr_static;
+/// #
+/// zstr_static!(#[no_mangle] pub static PLUGIN_NAME = "foo");
+///
+/// assert_eq!(&PLUGIN_NAME, b"foo\0");
+/// ```
+///
+/// Strings with embedded NUL bytes are not allowed:
+///
+/// ```compile_fail
+/// # use zstr::zstr_static;
+/// #
+/// zstr_static!(static INVALID = "nul \0 here";);
+/// ```
+#[proc_macro]
+pub fn zstr_static(input: TokenStream) -> TokenStream {
+    statics::expand_zstr_static(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Evaluates to the length of the C string that `zstr!()` would generate
+/// from the same literal, in bytes and excluding the terminating 0, as a
+/// `usize` literal (i.e. what `strlen()` would return). The literal may
+/// have an encoding prefix, just like in `zstr!()`.
+///
+/// Since the result is a literal, it can be used anywhere a constant is
+/// needed, e.g. for declaring fixed-size buffers. Add 1 to account for
+/// the terminating 0.
+///
+/// ### Examples:
+///
+/// ```
+/// use zstr::{ zstr, zstr_len };
+///
+/// const LEN: usize = zstr_len!("Hello 🎉");
+/// assert_eq!(LEN, 10);
+/// assert_eq!(LEN, zstr!("Hello 🎉").to_bytes().len());
+///
+/// let buffer = [0u8; zstr_len!(latin1: "café") + 1];
+/// assert_eq!(buffer.len(), 5);
+/// ```
+///
+/// ```compile_fail
+/// # use zstr::zstr_len;
+/// #
+/// let invalid = zstr_len!("nul \0 here");
+/// ```
+#[proc_macro]
+pub fn zstr_len(input: TokenStream) -> TokenStream {
+    expand_zstr_len(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Performs the actual expansion of `zstr_len!()`.
+fn expand_zstr_len(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let (prefix, literal) = Parser::parse2(
+        |input: ParseStream| Ok((input.parse::<EncodingPrefix>()?, input.parse::<Lit>()?)),
+        input,
+    )?;
+    let bytes = encoded_literal_bytes(prefix.encoding, &literal)?;
+    let len = LitInt::new(&format!("{}usize", bytes.len()), literal.span());
+
+    Ok(len.into_token_stream())
+}
+
+/// Generates an array of type `[c_char; N]` from a string literal, padded
+/// with 0 bytes, for initializing fixed-size character arrays embedded in
+/// C structs, such as `sockaddr_un::sun_path` or `ifreq::ifr_name`. The
+/// resulting expression can be used in `const` context.
+///
+/// The first argument is the size `N` of the array, and the last one is
+/// the string literal, with an optional encoding prefix as in `zstr!()`.
+/// The string must not contain any NUL bytes. In between, the following
+/// options may be given, separated by commas:
+///
+/// * `error` (the default): if the string does not fit into the array
+///   together with its terminating 0, compilation fails.
+/// * `truncate`: if the string does not fit, it is truncated to `N - 1`
+///   bytes, so that the array is still 0-terminated (like `strlcpy()`).
+///   Truncation happens at the byte level, so it may split a multi-byte
+///   character. To keep this auditable, a deprecation warning is emitted
+///   whenever a string is actually truncated, which can be silenced by
+///   `#[allow(deprecated)]` where it is intended.
+/// * `pad_with = b'x'`: the bytes after the terminating 0 are filled with
+///   the specified byte (a byte or integer literal) instead of 0.
+///
+/// Like `zstr!()`, this macro accepts a leading `crate = path,` argument.
+///
+/// ### Examples:
+///
+/// ```
+/// use std::os::raw::c_char;
+/// use zstr::zarray;
+///
+/// const IFNAME: [c_char; 16] = zarray!(16, "eth0");
+/// assert_eq!(IFNAME[..5], [b'e', b't', b'h', b'0', 0].map(|b| b as c_char));
+/// assert!(IFNAME[4..].iter().all(|&c| c == 0));
+///
+/// let exact: [c_char; 5] = zarray!(5, latin1: "café");
+/// assert_eq!(exact[3] as u8, 0xE9);
+///
+/// #[allow(deprecated)]
+/// let truncated: [c_char; 4] = zarray!(4, truncate, "eth0");
+/// assert_eq!(truncated, [b'e', b't', b'h', 0].map(|b| b as c_char));
+///
+/// let padded: [c_char; 6] = zarray!(6, truncate, pad_with = 0xFF, "ab");
+/// assert_eq!(padded.map(|c| c as u8), [b'a', b'b', 0, 0xFF, 0xFF, 0xFF]);
+/// ```
+///
+/// Strings that are too long are rejected by default:
+///
+/// ```compile_fail
+/// # use zstr::zarray;
+/// #
+/// let too_long = zarray!(4, "eth0");
+/// ```
+///
+/// With `truncate`, a warning is emitted instead:
+///
+/// ```compile_fail
+/// #![deny(deprecated)]
+/// # use zstr::zarray;
+/// #
+/// let truncated = zarray!(4, truncate, "eth0");
+/// ```
+#[proc_macro]
+pub fn zarray(input: TokenStream) -> TokenStream {
+    fixed::expand_zarray(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derives an inherent `const fn as_cstr(&self) -> &'static CStr` method
+/// for an enum, which returns the name of the variant as a C string, e.g.
+/// for passing it to C logging functions or registering it with GObject.
+///
+/// By default, the name of a variant is its identifier. This can be
+/// changed for all variants using `#[zstr(rename_all = "...")]` on the
+/// enum, where the case convention is one of `lowercase`, `UPPERCASE`,
+/// `PascalCase`, `camelCase`, `snake_case`, `SCREAMING_SNAKE_CASE`,
+/// `kebab-case`, or `SCREAMING-KEBAB-CASE`. The name of an individual
+/// variant can be overridden using `#[zstr(rename = "...")]`. Names must
+/// not contain NUL bytes. Variants may have fields, which are ignored.
+///
+/// The path to `CStr` can be overridden using `#[zstr(crate = path)]`
+/// on the enum, just like the `crate = path,` argument of `zstr!()`.
+///
+/// ### Examples:
+///
+/// ```
+/// use zstr::ZstrName;
+///
+/// #[derive(ZstrName)]
+/// #[zstr(rename_all = "SCREAMING_SNAKE_CASE")]
+/// enum LogLevel {
+///     Debug,
+///     SevereWarning,
+///     #[zstr(rename = "FATAL!")]
+///     Fatal { code: i32 },
+/// }
+///
+/// assert_eq!(LogLevel::Debug.as_cstr().to_bytes(), b"DEBUG");
+/// assert_eq!(LogLevel::SevereWarning.as_cstr().to_bytes(), b"SEVERE_WARNING");
+/// assert_eq!(LogLevel::Fatal { code: 1 }.as_cstr().to_bytes(), b"FATAL!");
+///
+/// const NAME: &std::ffi::CStr = LogLevel::Debug.as_cstr();
+/// assert_eq!(NAME.to_bytes(), b"DEBUG");
+/// ```
+///
+/// ```compile_fail
+/// # use zstr::ZstrName;
+/// #
+/// #[derive(ZstrName)]
+/// enum Invalid {
+///     #[zstr(rename = "nul \0 here")]
+///     Variant,
+/// }
+/// ```
+#[proc_macro_derive(ZstrName, attributes(zstr))]
+pub fn derive_zstr_name(input: TokenStream) -> TokenStream {
+    derive::expand_zstr_name(parse_macro_input!(input as DeriveInput))
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derives `TryFrom<&CStr>` for an enum, mapping the C string name of
+/// a variant back to the variant, e.g. when a C callback passes a name
+/// as a `const char *`. Unrecognized names are returned as the error.
+///
+/// Variant names are determined in the same way as by
+/// [`ZstrName`](derive.ZstrName.html), so the two derives can be used
+/// together. They are validated at compile time, and they must be unique.
+/// Only enums whose variants have no fields are supported.
+///
+/// Names are looked up using binary search in a table sorted at compile
+/// time, so the lookup takes logarithmic time in the number of variants.
+///
+/// ### Examples:
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use zstr::{ zstr, ZstrName, ZstrFromName };
+///
+/// #[derive(ZstrName, ZstrFromName, PartialEq, Debug)]
+/// #[zstr(rename_all = "kebab-case")]
+/// enum Signal {
+///     Clicked,
+///     KeyPressed,
+///     #[zstr(rename = "destroy")]
+///     Destroyed,
+/// }
+///
+/// assert_eq!(Signal::try_from(zstr!("key-pressed")), Ok(Signal::KeyPressed));
+/// assert_eq!(Signal::try_from(zstr!("destroy")), Ok(Signal::Destroyed));
+/// assert_eq!(Signal::try_from(zstr!("destroyed")), Err(zstr!("destroyed")));
+///
+/// let name = Signal::Clicked.as_cstr();
+/// assert_eq!(Signal::try_from(name), Ok(Signal::Clicked));
+/// ```
+///
+/// ```compile_fail
+/// # use zstr::ZstrFromName;
+/// #
+/// #[derive(ZstrFromName)]
+/// enum Duplicate {
+///     First,
+///     #[zstr(rename = "First")]
+///     Second,
+/// }
+/// ```
+#[proc_macro_derive(ZstrFromName, attributes(zstr))]
+pub fn derive_zstr_from_name(input: TokenStream) -> TokenStream {
+    derive::expand_zstr_from_name(parse_macro_input!(input as DeriveInput))
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Rewrites string literals passed directly as arguments to foreign
+/// functions into `*const c_char` pointers, as if they were written as
+/// `zstr!("...").as_ptr()`. This can be applied to any item, e.g. a
+/// function, an `impl` block, or an inline module.
+///
+/// A call is considered a call to a foreign function if:
+///
+/// * the function is called by its bare name, e.g. `puts(...)`, and a
+///   function of that name is declared in an `extern` block within the
+///   annotated item (qualified paths like `other::puts(...)` are not
+///   considered, since they may refer to a Rust function), or
+/// * the path of the function starts with one of the paths given as
+///   arguments of the attribute, e.g. `#[zstr::auto(libc)]` rewrites the
+///   arguments of `libc::puts("...")`, and `#[zstr::auto(libc::puts)]`
+///   only those of `libc::puts` itself.
+///
+/// Literals are validated just like by `zstr!()`; all embedded NUL
+/// bytes are reported at once. Like other attribute macros, this can not
+/// see into the arguments of macro invocations, e.g. `println!()`, and
+/// other kinds of literals (as well as string literals passed to Rust
+/// functions) are left unchanged.
+///
+/// The path to `CStr` can be overridden using a leading `crate = path`
+/// argument, just like with `zstr!()`.
+///
+/// ### Examples:
+///
+/// ```
+/// # #![deny(unused_unsafe)]
+/// #[zstr::auto]
+/// mod ffi {
+///     use std::os::raw::{ c_char, c_int };
+///
+///     extern "C" {
+///         fn strlen(s: *const c_char) -> usize;
+///         fn strcmp(a: *const c_char, b: *const c_char) -> c_int;
+///     }
+///
+///     pub fn test() {
+///         let (len, bytes_len, cmp) = unsafe {
+///             (strlen("hello"), strlen(b"bytes"), strcmp("abc", "abc"))
+///         };
+///
+///         assert_eq!(len, 5);
+///         assert_eq!(bytes_len, 5);
+///         assert_eq!(cmp, 0);
+///
+///         // Not a foreign function, despite having the same name.
+///         let rust_len = rust::strlen("hello");
+///         assert_eq!(rust_len, 5);
+///     }
+///
+///     mod rust {
+///         pub fn strlen(s: &str) -> usize {
+///             s.len()
+///         }
+///     }
+/// }
+///
+/// ffi::test();
+/// ```
+///
+/// This also works with `unsafe extern` blocks, which are required in
+/// edition 2024, including items in them that are marked `safe`:
+///
+/// ```edition2024
+/// use std::ffi::{ c_char, c_int };
+///
+/// #[zstr::auto]
+/// fn check() {
+///     unsafe extern "C" {
+///         fn strlen(s: *const c_char) -> usize;
+///         pub unsafe fn strcmp(a: *const c_char, b: *const c_char) -> c_int;
+///         safe fn abs(n: c_int) -> c_int;
+///     }
+///
+///     let (len, cmp) = unsafe { (strlen("four"), strcmp("a", "a")) };
+///     assert_eq!(len, 4);
+///     assert_eq!(cmp, 0);
+///     assert_eq!(abs(-3), 3);
+/// }
+///
+/// check();
+/// ```
+///
+/// ```
+/// mod sys {
+///     pub unsafe fn count(s: *const std::os::raw::c_char) -> usize {
+///         std::ffi::CStr::from_ptr(s).to_bytes().len()
+///     }
+/// }
+///
+/// #[zstr::auto(sys)]
+/// fn test() {
+///     let count = unsafe { sys::count("four") };
+///     assert_eq!(count, 4);
+/// }
+///
+/// test();
+/// ```
+///
+/// ```compile_fail
+/// #[zstr::auto]
+/// fn invalid() {
+///     extern "C" {
+///         fn puts(s: *const std::os::raw::c_char) -> std::os::raw::c_int;
+///     }
+///
+///     unsafe { puts("nul \0 here"); }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn auto(args: TokenStream, item: TokenStream) -> TokenStream {
+    auto::expand_auto(args.into(), item.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Defines interned C strings, which can then be referred to using
+/// `zstr_intern!()` from any crate linked into the same binary. Every use
+/// of the same string refers to the same static, so interned strings
+/// can be compared by address, e.g. by C APIs that use `const char *`
+/// pointers as identifiers.
+///
+/// Each string is emitted as a static with an exported symbol, whose name
+/// is derived from the length and a hash of the contents. Therefore, each
+/// string must be defined exactly once in the final binary: defining it
+/// in more than one crate results in a duplicate symbol error, and using
+/// it without defining it results in an undefined symbol error at link
+/// time. It is customary to define all interned strings in one crate, which
+/// must then be linked into the binary, e.g. using `use strings_crate as _;`.
+///
+/// The strings may have encoding prefixes, just like with `zstr!()`.
+///
+/// ### Examples:
+///
+/// ```
+/// use zstr::{ zstr_interned, zstr_intern };
+///
+/// zstr_interned! {
+///     "clicked",
+///     "destroy",
+///     latin1: "caf\u{e9}",
+/// }
+///
+/// let first = zstr_intern!("clicked");
+/// let second = zstr_intern!("clicked");
+///
+/// assert_eq!(first.to_bytes(), b"clicked");
+/// assert_eq!(first.as_ptr(), second.as_ptr());
+/// assert_eq!(zstr_intern!(latin1: "caf\u{e9}").to_bytes(), b"caf\xe9");
+/// ```
+///
+/// ```compile_fail
+/// # use zstr::zstr_interned;
+/// #
+/// zstr_interned! {
+///     "same",
+///     "same",
+/// }
+/// ```
+#[proc_macro]
+pub fn zstr_interned(input: TokenStream) -> TokenStream {
+    intern::expand_zstr_interned(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Refers to a C string defined by `zstr_interned!()`, which may be in a
+/// different crate, and returns it as a `&'static CStr`. Unlike `zstr!()`,
+/// this can not be used in constant expressions, because the address of
+/// the string is only known at link time.
+///
+/// The path to `CStr` can be overridden using a leading `crate = path,`
+/// argument, just like with `zstr!()`. See `zstr_interned!()` for examples.
+///
+/// ### Examples:
+///
+/// ```
+/// # use zstr::{ zstr_interned, zstr_intern };
+/// #
+/// zstr_interned!("quark");
+///
+/// fn quark() -> *const std::os::raw::c_char {
+///     zstr_intern!("quark").as_ptr()
+/// }
+///
+/// assert_eq!(quark(), zstr_intern!("quark").as_ptr());
+/// ```
+#[proc_macro]
+pub fn zstr_intern(input: TokenStream) -> TokenStream {
+    intern::expand_zstr_intern(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Computes a hash of the bytes of a C string literal at compile time,
+/// and expands to an integer literal, so it can be used as a pattern in
+/// `match` expressions, e.g. for dispatching on strings received from C.
+///
+/// The first argument selects the hash function, which is one of:
+///
+/// * `fnv1a32`: 32-bit FNV-1a, expanding to a `u32`;
+/// * `fnv1a64`: 64-bit FNV-1a, expanding to a `u64`;
+/// * `crc32`: CRC-32 as used by zlib and PNG, expanding to a `u32`;
+/// * `xxh32`: 32-bit xxHash with a seed of 0, expanding to a `u32`.
+///
+/// By default, only the bytes of the string are hashed, i.e. what
+/// `CStr::to_bytes()` returns. If the optional `with_nul` argument is
+/// given, the terminating 0 is hashed too, i.e. what
+/// `CStr::to_bytes_with_nul()` returns. The literal may have an encoding
+/// prefix, just like with `zstr!()`, in which case the encoded bytes are
+/// hashed.
+///
+/// ### Examples:
+///
+/// ```
+/// use zstr::zstr_hash;
+///
+/// assert_eq!(zstr_hash!(fnv1a32, "a"), 0xe40c292c);
+/// assert_eq!(zstr_hash!(fnv1a64, "a"), 0xaf63dc4c8601ec8c);
+/// assert_eq!(zstr_hash!(crc32, "123456789"), 0xcbf43926);
+/// assert_eq!(zstr_hash!(xxh32, ""), 0x02cc5d05);
+/// assert_eq!(zstr_hash!(xxh32, "Nobody inspects the spammish repetition"), 0xe2293b2f);
+/// assert_eq!(zstr_hash!(crc32, with_nul, "abc"), 0xa75d6850);
+/// assert_eq!(zstr_hash!(fnv1a32, latin1: "\u{e9}"), zstr_hash!(fnv1a32, b"\xe9"));
+///
+/// fn dispatch(command: &std::ffi::CStr) -> &'static str {
+///     match fnv1a32(command.to_bytes()) {
+///         zstr_hash!(fnv1a32, "start") => "starting",
+///         zstr_hash!(fnv1a32, "stop") => "stopping",
+///         _ => "unknown",
+///     }
+/// }
+///
+/// fn fnv1a32(bytes: &[u8]) -> u32 {
+///     bytes.iter().fold(0x811c9dc5, |h, &b| (h ^ u32::from(b)).wrapping_mul(0x01000193))
+/// }
+///
+/// assert_eq!(dispatch(zstr::zstr!("stop")), "stopping");
+/// assert_eq!(dispatch(zstr::zstr!("pause")), "unknown");
+/// ```
+///
+/// ```compile_fail
+/// # use zstr::zstr_hash;
+/// #
+/// let hash = zstr_hash!(md5, "digest");
+/// ```
+#[proc_macro]
+pub fn zstr_hash(input: TokenStream) -> TokenStream {
+    hash::expand_zstr_hash(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Like `zstr_hash!()`, but expands to a tuple of the C string itself,
+/// as a `&'static CStr`, and its hash. Both are computed from the same
+/// bytes, so they are guaranteed to agree. This can also be used in
+/// constant expressions.
+///
+/// The path to `CStr` can be overridden using a leading `crate = path,`
+/// argument, just like with `zstr!()`.
+///
+/// ### Examples:
+///
+/// ```
+/// use std::ffi::CStr;
+/// use zstr::{ zstr_hash, zstr_with_hash };
+///
+/// const COMMAND: (&CStr, u32) = zstr_with_hash!(crc32, with_nul, "reload");
+///
+/// assert_eq!(COMMAND.0.to_bytes(), b"reload");
+/// assert_eq!(COMMAND.1, 0xc9a1f238);
+/// assert_ne!(COMMAND.1, zstr_hash!(crc32, "reload"));
+/// ```
+#[proc_macro]
+pub fn zstr_with_hash(input: TokenStream) -> TokenStream {
+    hash::expand_zstr_with_hash(input.into())
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Returns an error if `units` contains a 0 (NUL) element. `what` is the
+/// kind of string and `unit` is the kind of its elements, used in the
+/// error message.
+fn ensure_no_nul<T>(units: &[T], span: Span, what: &str, unit: &str) -> Result<(), Error>
+where
+    T: Copy + Default + PartialEq,
+{
+    match units.iter().position(|&u| u == T::default()) {
+        Some(index) => {
+            let message = format!("{} contains an embedded NUL {} at index {}", what, unit, index);
+            Err(Error::new(span, message))
+        }
+        None => Ok(())
+    }
+}
+
+/// Returns the span of the bytes in `range` of the value of a string or
+/// byte string literal. Since this is only supported by some compilers,
+/// and only if the literal contains no escapes, this falls back to the
+/// span of the whole literal.
+fn literal_subspan(literal: &Lit, range: Range<usize>) -> Span {
+    let span = literal.span();
+    let (token, value) = match literal {
+        Lit::Str(lit) => (lit.token(), lit.value().into_bytes()),
+        Lit::ByteStr(lit) => (lit.token(), lit.value()),
+        _ => return span,
+    };
+
+    // Find the contents of the literal between the quotes, skipping
+    // the prefix (e.g. `b` or `r#`) and the suffix.
+    let text = token.to_string();
+    let (start, end) = match (text.find('"'), text.rfind('"')) {
+        (Some(start), Some(end)) if start < end => (start + 1, end),
+        _ => return span,
+    };
+
+    if text.as_bytes()[start..end] != value[..] {
+        return span;
+    }
+
+    token.subspan(start + range.start..start + range.end).unwrap_or(span)
 }
@@ -0,0 +1,41 @@
+//! Double-NUL-terminated lists of strings.
+
+use proc_macro2::{ Span, TokenStream as TokenStream2 };
+use syn::{ Error, Lit, LitByteStr, Token };
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use quote::quote_spanned;
+use crate::literal_bytes;
+
+/// Performs the actual expansion of `zstr_multi!()`.
+pub fn expand_zstr_multi(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let literals = Punctuated::<Lit, Token![,]>::parse_terminated.parse2(input)?;
+    let mut bytes = Vec::new();
+
+    for literal in &literals {
+        let item = literal_bytes(literal)?;
+
+        // An empty item would be mistaken for the end of the list.
+        if item.is_empty() {
+            let message = "string list item is empty, which would terminate the list";
+            return Err(Error::new(literal.span(), message));
+        }
+
+        bytes.extend(item);
+        bytes.push(0x00);
+    }
+
+    // Add the terminating 0 of the list itself.
+    bytes.push(0x00);
+
+    let span = literals.first().map_or_else(Span::call_site, Lit::span);
+    let bstr = LitByteStr::new(&bytes, span);
+
+    // Expand to an expression of type `&'static [u8]`.
+    Ok(quote_spanned!{
+        span => {
+            const MULTI: &[u8] = #bstr;
+            MULTI
+        }
+    })
+}
@@ -0,0 +1,111 @@
+//! Support for built-in macro invocations as the input of `zstr!()`.
+//!
+//! The output of these macros is not available to a procedural macro,
+//! so instead of evaluating them eagerly, `zstr!()` expands to a constant
+//! expression that copies their output into a 0-terminated array, and
+//! checks for embedded NUL bytes during constant evaluation.
+
+use proc_macro2::{ Span, TokenStream as TokenStream2 };
+use syn::{ Error, Macro };
+use quote::quote_spanned;
+use crate::ffi::FfiRoot;
+
+/// The built-in macros accepted by `zstr!()`.
+pub const SUPPORTED_MACROS: &[&str] = &[
+    "concat",
+    "stringify",
+    "env",
+    "option_env",
+    "include_str",
+    "include_bytes",
+];
+
+/// Expands `zstr!(mac!(...))` for one of the `SUPPORTED_MACROS`.
+pub fn expand_nested(root: &FfiRoot, mac: Macro) -> Result<TokenStream2, Error> {
+    let span = mac.path.segments.last().map_or_else(Span::call_site, |s| s.ident.span());
+    let name = mac.path.segments.last().map(|s| s.ident.to_string()).unwrap_or_default();
+    let cstr = root.cstr(span);
+
+    let (bytes, value) = match name.as_str() {
+        "concat" | "stringify" | "env" | "include_str" => (
+            quote_spanned!(span => #mac.as_bytes()),
+            quote_spanned!(span => CSTR),
+        ),
+        "include_bytes" => (
+            quote_spanned!(span => #mac),
+            quote_spanned!(span => CSTR),
+        ),
+        "option_env" => return Ok(expand_option_env(&cstr, mac, span)),
+        _ => {
+            let message = format!(
+                "expected a string or byte string literal, or an invocation of one of: {}!",
+                SUPPORTED_MACROS.join("!, "),
+            );
+            return Err(Error::new_spanned(mac.path, message));
+        }
+    };
+
+    let array = nul_terminated_array(bytes, span);
+
+    Ok(quote_spanned!{
+        span => {
+            #array
+            // SAFETY: `ARRAY` is NUL-terminated, and it was checked
+            // during constant evaluation that it does not contain any
+            // other, internal NUL bytes.
+            const CSTR: &#cstr = unsafe {
+                #cstr::from_bytes_with_nul_unchecked(&ARRAY)
+            };
+            #value
+        }
+    })
+}
+
+/// Expands `zstr!(option_env!(...))` to an `Option<&'static CStr>`.
+fn expand_option_env(cstr: &TokenStream2, mac: Macro, span: Span) -> TokenStream2 {
+    let array = nul_terminated_array(
+        quote_spanned!(span => match STR {
+            Some(s) => s.as_bytes(),
+            None => b"",
+        }),
+        span,
+    );
+
+    quote_spanned!{
+        span => {
+            const STR: ::core::option::Option<&str> = #mac;
+            #array
+            // SAFETY: see `expand_nested()`.
+            const CSTR: ::core::option::Option<&#cstr> = match STR {
+                Some(_) => Some(unsafe { #cstr::from_bytes_with_nul_unchecked(&ARRAY) }),
+                None => None,
+            };
+            CSTR
+        }
+    }
+}
+
+/// Generates a constant named `ARRAY` that contains the bytes of the
+/// constant expression `bytes` (of type `&'static [u8]`), followed by
+/// a terminating 0. Constant evaluation fails if `bytes` contains a
+/// NUL byte.
+fn nul_terminated_array(bytes: TokenStream2, span: Span) -> TokenStream2 {
+    quote_spanned!{
+        span =>
+        const BYTES: &[u8] = #bytes;
+        const ARRAY: [u8; BYTES.len() + 1] = {
+            let mut array = [0x00; BYTES.len() + 1];
+            let mut i = 0;
+
+            while i < BYTES.len() {
+                if BYTES[i] == 0x00 {
+                    panic!("C string contains an embedded NUL byte");
+                }
+                array[i] = BYTES[i];
+                i += 1;
+            }
+
+            array
+        };
+    }
+}
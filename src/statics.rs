@@ -0,0 +1,70 @@
+//! Named statics containing C strings, for exporting them to C.
+
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{ Attribute, Error, Ident, Lit, LitByteStr, Token, Visibility };
+use syn::parse::{ Parse, Parser, ParseStream };
+use quote::quote_spanned;
+use crate::encoding::{ EncodingPrefix, encoded_literal_bytes };
+
+/// A single `static` item in the input of `zstr_static!()`.
+struct StaticItem {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    prefix: EncodingPrefix,
+    literal: Lit,
+}
+
+impl Parse for StaticItem {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        input.parse::<Token![static]>()?;
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let prefix = input.parse()?;
+        let literal = input.parse()?;
+
+        // The semicolon may be omitted after the last item.
+        if !input.is_empty() {
+            input.parse::<Token![;]>()?;
+        }
+
+        Ok(StaticItem { attrs, vis, name, prefix, literal })
+    }
+}
+
+/// Performs the actual expansion of `zstr_static!()`.
+pub fn expand_zstr_static(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let items = Parser::parse2(
+        |input: ParseStream| {
+            let mut items = Vec::new();
+            while !input.is_empty() {
+                items.push(input.parse::<StaticItem>()?);
+            }
+            Ok(items)
+        },
+        input,
+    )?;
+
+    items.into_iter().map(expand_static).collect()
+}
+
+/// Expands a single `static` item to a `[u8; N]` array.
+fn expand_static(item: StaticItem) -> Result<TokenStream2, Error> {
+    let StaticItem { attrs, vis, name, prefix, literal } = item;
+    let span = literal.span();
+    let mut bytes = encoded_literal_bytes(prefix.encoding, &literal)?;
+
+    // Add the terminating 0.
+    bytes.push(0x00);
+
+    let len = bytes.len();
+    let bstr = LitByteStr::new(&bytes, span);
+
+    Ok(quote_spanned!{
+        span =>
+        #(#attrs)*
+        #vis static #name: [u8; #len] = *#bstr;
+    })
+}
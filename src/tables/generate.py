@@ -0,0 +1,81 @@
+#!/usr/bin/env python3
+"""
+Generates the encoding tables used by `src/cjk.rs` from the codecs
+of the Python standard library. Run from this directory:
+
+    python3 generate.py
+
+Each `*.bin` file is a sequence of big-endian `(u16, u16)` records,
+sorted by the first field, which is a BMP code point. The second
+field is the encoded form of the code point: a single byte if it is
+less than 0x100, otherwise a lead byte (high 8 bits) and a trail byte
+(low 8 bits).
+
+`gb18030.bin` only contains the two-byte mappings in which GB 18030
+differs from GBK. `gb18030_ranges.bin` is a sequence of big-endian
+`(u32, u32)` records: the first code point of a run of characters
+encoded as four bytes, and the linear index of its four-byte code.
+"""
+
+import struct
+
+
+def mappings(encoding, max_len):
+    """All non-ASCII BMP code points encoded using at most `max_len` bytes."""
+    table = {}
+    for cp in range(0x80, 0x10000):
+        if 0xD800 <= cp < 0xE000:
+            continue
+        try:
+            encoded = chr(cp).encode(encoding)
+        except UnicodeEncodeError:
+            continue
+        if len(encoded) <= max_len:
+            table[cp] = encoded
+    return table
+
+
+def write_pairs(path, table):
+    with open(path, 'wb') as f:
+        for cp in sorted(table):
+            f.write(struct.pack('>HH', cp, int.from_bytes(table[cp], 'big')))
+
+
+def linear_index(code):
+    b1, b2, b3, b4 = code
+    return (((b1 - 0x81) * 10 + (b2 - 0x30)) * 126 + (b3 - 0x81)) * 10 + (b4 - 0x30)
+
+
+def gb18030_ranges():
+    ranges = []
+    for cp in range(0x80, 0x10000):
+        if 0xD800 <= cp < 0xE000:
+            continue
+        encoded = chr(cp).encode('gb18030')
+        if len(encoded) != 4:
+            continue
+        index = linear_index(encoded)
+        if ranges:
+            start_cp, start_index = ranges[-1]
+            if index - start_index == cp - start_cp:
+                continue
+        ranges.append((cp, index))
+    return ranges
+
+
+def main():
+    write_pairs('shift_jis.bin', mappings('shift_jis', 2))
+    write_pairs('euc_kr.bin', mappings('euc_kr', 2))
+
+    gbk = mappings('gbk', 2)
+    gb18030 = mappings('gb18030', 2)
+    write_pairs('gbk.bin', gbk)
+    write_pairs('gb18030.bin', {cp: code for cp, code in gb18030.items() if gbk.get(cp) != code})
+
+    with open('gb18030_ranges.bin', 'wb') as f:
+        for cp, index in gb18030_ranges():
+            f.write(struct.pack('>II', cp, index))
+
+
+if __name__ == '__main__':
+    main()
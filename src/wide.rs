@@ -0,0 +1,42 @@
+//! Wide (UTF-16 and UTF-32) string literals.
+
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{ parse2, Error, LitStr };
+use quote::{ quote, quote_spanned, ToTokens };
+use crate::ensure_no_nul;
+
+/// Performs the actual expansion of `wzstr!()`.
+pub fn expand_wzstr(input: TokenStream2) -> Result<TokenStream2, Error> {
+    expand_wide(input, |s| s.encode_utf16().collect::<Vec<u16>>(), quote!(u16))
+}
+
+/// Performs the actual expansion of `wzstr32!()`.
+pub fn expand_wzstr32(input: TokenStream2) -> Result<TokenStream2, Error> {
+    expand_wide(input, |s| s.chars().map(u32::from).collect::<Vec<u32>>(), quote!(u32))
+}
+
+/// Encodes a string literal into code units of type `ty` using `encode`,
+/// then expands to an expression of type `&'static [ty]` that is
+/// terminated by a single 0 code unit.
+fn expand_wide<F, T>(input: TokenStream2, encode: F, ty: TokenStream2) -> Result<TokenStream2, Error>
+where
+    F: FnOnce(&str) -> Vec<T>,
+    T: Copy + Default + PartialEq + ToTokens,
+{
+    let literal: LitStr = parse2(input)?;
+    let span = literal.span();
+    let mut units = encode(&literal.value());
+
+    ensure_no_nul(&units, span, "wide string", "code unit")?;
+
+    // Add the terminating 0.
+    units.push(T::default());
+
+    // Expand to an expression of type `&'static [T]`.
+    Ok(quote_spanned!{
+        span => {
+            const WIDE: &[#ty] = &[#(#units),*];
+            WIDE
+        }
+    })
+}
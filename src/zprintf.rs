@@ -0,0 +1,230 @@
+//! Type-checked calls to variadic C formatting functions.
+
+use proc_macro2::{ Span, TokenStream as TokenStream2 };
+use syn::{ Error, Expr, Ident, Lit, Token };
+use syn::parse::{ Parse, Parser, ParseStream };
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use quote::{ quote, quote_spanned, ToTokens };
+use crate::ffi::FfiRoot;
+use crate::format::{ validate_format, ArgKind };
+use crate::cstr_expr;
+
+/// The input of `zprintf!()`.
+struct ZprintfInput {
+    root: FfiRoot,
+    func: Expr,
+    format: Lit,
+    args: Vec<Expr>,
+}
+
+impl Parse for ZprintfInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let root = input.parse()?;
+        let func = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let format = input.parse()?;
+        let args = if input.is_empty() {
+            Vec::new()
+        } else {
+            input.parse::<Token![,]>()?;
+            Punctuated::<Expr, Token![,]>::parse_terminated(input)?.into_iter().collect()
+        };
+
+        Ok(ZprintfInput { root, func, format, args })
+    }
+}
+
+/// Performs the actual expansion of `zprintf!()`.
+pub fn expand_zprintf(input: TokenStream2) -> Result<TokenStream2, Error> {
+    let ZprintfInput { root, func, format, args } = ZprintfInput::parse.parse2(input)?;
+    let span = format.span();
+    let (bytes, directives) = validate_format(&format)?;
+    let kinds: Vec<ArgKind> = directives.iter().flat_map(|d| d.args.iter().copied()).collect();
+
+    if kinds.len() != args.len() {
+        let message = format!(
+            "format string consumes {} argument(s), but {} were given",
+            kinds.len(),
+            args.len(),
+        );
+        let span = args.get(kinds.len()).map_or(span, Spanned::span);
+        return Err(Error::new(span, message));
+    }
+
+    // If the function is given as a call expression, its arguments
+    // are passed before the format string, e.g. for `snprintf()`.
+    let (func, leading) = match func {
+        Expr::Call(call) => (*call.func, call.args.into_iter().collect()),
+        func => (func, Vec::new()),
+    };
+
+    // The arguments, including the function itself, are evaluated in
+    // the scrutinee of a `match`, outside the scope of the generated
+    // items, so that they can not accidentally refer to those items.
+    // This also keeps temporaries in the arguments alive for the call.
+    // Identifiers in patterns resolve to constants in scope even with
+    // mixed-site hygiene, hence the unusual names of the bindings.
+    // The bindings are located at the arguments for better diagnostics.
+    let binding = |name: &str, i: usize, span: Span| {
+        Ident::new(&format!("__zstr_{}{}", name, i), Span::mixed_site().located_at(span))
+    };
+    let func_binding = binding("func", 0, func.span());
+    let leading_bindings: Vec<_> = leading.iter().enumerate().map(|(i, e)| binding("leading", i, e.span())).collect();
+    let arg_bindings: Vec<_> = args.iter().enumerate().map(|(i, e)| binding("arg", i, e.span())).collect();
+
+    let mut helpers = Helpers::default();
+    let (values, converted): (Vec<_>, Vec<_>) = kinds
+        .iter()
+        .zip(args)
+        .zip(&arg_bindings)
+        .map(|((&kind, arg), binding)| convert_arg(&root, kind, arg, binding, &mut helpers))
+        .collect::<Result<Vec<_>, Error>>()?
+        .into_iter()
+        .unzip();
+    let helpers = helpers.expand(&root);
+    let ty = root.cstr(span);
+    let format = cstr_expr(&root, bytes, span);
+
+    // The format string is defined as a constant, because item bodies
+    // do not inherit the `unsafe` context of the caller, which would
+    // make the `unsafe` block in `format` redundant.
+    Ok(quote_spanned!{
+        Span::call_site() =>
+        match (#func, #(#leading,)* #(#values,)*) {
+            (#func_binding, #(#leading_bindings,)* #(#arg_bindings,)*) => {
+                #helpers
+                const FORMAT: &#ty = #format;
+                #func_binding(#(#leading_bindings,)* FORMAT.as_ptr(), #(#converted),*)
+            }
+        }
+    })
+}
+
+/// The helper traits that need to be emitted for converting arguments.
+#[derive(Default)]
+struct Helpers {
+    /// Converts `&CStr` and `char` pointers to `*const c_char`.
+    string: bool,
+    /// Converts any raw pointer to `*const c_void`.
+    pointer: bool,
+}
+
+impl Helpers {
+    /// Emits the definitions of the helper traits that are used.
+    fn expand(&self, root: &FfiRoot) -> TokenStream2 {
+        let span = Span::call_site();
+        let cstr = root.cstr(span);
+        let c_char = root.c_char(span);
+        let c_void = root.c_type("c_void", span);
+        let mut tokens = TokenStream2::new();
+
+        if self.string {
+            tokens.extend(quote!{
+                trait ZprintfStr {
+                    fn into_ptr(self) -> *const #c_char;
+                }
+
+                impl ZprintfStr for &#cstr {
+                    fn into_ptr(self) -> *const #c_char {
+                        self.as_ptr()
+                    }
+                }
+
+                impl ZprintfStr for *const #c_char {
+                    fn into_ptr(self) -> *const #c_char {
+                        self
+                    }
+                }
+
+                impl ZprintfStr for *mut #c_char {
+                    fn into_ptr(self) -> *const #c_char {
+                        self as *const #c_char
+                    }
+                }
+            });
+        }
+
+        if self.pointer {
+            tokens.extend(quote!{
+                trait ZprintfPtr {
+                    fn into_ptr(self) -> *const #c_void;
+                }
+
+                impl<T> ZprintfPtr for *const T {
+                    fn into_ptr(self) -> *const #c_void {
+                        self as *const #c_void
+                    }
+                }
+
+                impl<T> ZprintfPtr for *mut T {
+                    fn into_ptr(self) -> *const #c_void {
+                        self as *const #c_void
+                    }
+                }
+            });
+        }
+
+        tokens
+    }
+}
+
+/// Converts an argument to the type expected by the format string,
+/// causing a type error if it has a different Rust type. Returns the
+/// expression evaluating the argument, and the expression passing its
+/// value, which is bound to `binding`, to the function.
+fn convert_arg(
+    root: &FfiRoot,
+    kind: ArgKind,
+    arg: Expr,
+    binding: &Ident,
+    helpers: &mut Helpers,
+) -> Result<(TokenStream2, TokenStream2), Error> {
+    let span = arg.span();
+    let exact = |ty: TokenStream2| quote_spanned!(span => ::core::convert::identity::<#ty>(#arg));
+    let c_type = |name: &str| root.c_type(name, span);
+    let bound = quote!(#binding);
+
+    let converted = match kind {
+        // Types narrower than `int` are promoted to `int` when passed
+        // through varargs, so this needs to be done explicitly.
+        ArgKind::SChar | ArgKind::UChar | ArgKind::Short | ArgKind::UShort => {
+            let ty = c_type(match kind {
+                ArgKind::SChar => "c_schar",
+                ArgKind::UChar => "c_uchar",
+                ArgKind::Short => "c_short",
+                _ => "c_ushort",
+            });
+            let c_int = c_type("c_int");
+            let value = exact(ty);
+            (quote_spanned!(span => #value as #c_int), bound)
+        }
+        ArgKind::Int => (exact(c_type("c_int")), bound),
+        ArgKind::UInt => (exact(c_type("c_uint")), bound),
+        ArgKind::Long => (exact(c_type("c_long")), bound),
+        ArgKind::ULong => (exact(c_type("c_ulong")), bound),
+        ArgKind::LongLong => (exact(c_type("c_longlong")), bound),
+        ArgKind::ULongLong => (exact(c_type("c_ulonglong")), bound),
+        ArgKind::IntMax => (exact(quote_spanned!(span => i64)), bound),
+        ArgKind::UIntMax => (exact(quote_spanned!(span => u64)), bound),
+        ArgKind::Size => (exact(quote_spanned!(span => usize)), bound),
+        ArgKind::SSize | ArgKind::PtrDiff => (exact(quote_spanned!(span => isize)), bound),
+        ArgKind::Double => (exact(c_type("c_double")), bound),
+        // The helper traits are only in scope where the function is
+        // called, so the conversion is applied to the bound value.
+        ArgKind::Str => {
+            helpers.string = true;
+            (arg.into_token_stream(), quote_spanned!(span => ZprintfStr::into_ptr(#binding)))
+        }
+        ArgKind::Pointer => {
+            helpers.pointer = true;
+            (arg.into_token_stream(), quote_spanned!(span => ZprintfPtr::into_ptr(#binding)))
+        }
+        ArgKind::LongDouble | ArgKind::WInt | ArgKind::WideStr => {
+            let message = format!("arguments of type `{}` are not supported", kind.c_type());
+            return Err(Error::new(span, message));
+        }
+    };
+
+    Ok(converted)
+}
@@ -0,0 +1,27 @@
+//! Ensures that `zstr!()` expands to code that compiles without `std`.
+
+#![no_std]
+
+use core::ffi::CStr;
+use core::ffi::c_char;
+use zstr::{ zstr, zstr_array };
+
+const GREETING: &CStr = zstr!("Hello, no_std!");
+
+#[test]
+fn no_std_literal() {
+    assert_eq!(GREETING.to_bytes(), b"Hello, no_std!");
+    assert_eq!(zstr!(b"bytes").to_bytes_with_nul(), b"bytes\0");
+}
+
+#[test]
+fn no_std_crate_override() {
+    assert_eq!(zstr!(crate = ::core, "core").to_bytes(), b"core");
+}
+
+#[test]
+fn no_std_array() {
+    const ARGV: &[*const c_char] = zstr_array!["no", "std"];
+    assert_eq!(ARGV.len(), 3);
+    assert!(ARGV[2].is_null());
+}
@@ -0,0 +1,268 @@
+//! Double-NUL-terminated lists of C strings.
+//!
+//! A list in this format consists of zero or more non-empty strings,
+//! each followed by a 0 byte, and an additional terminating 0 byte
+//! after the last one. This is the format of e.g. Windows `REG_MULTI_SZ`
+//! values, environment blocks, and `GetOpenFileName()` filters.
+//!
+//! Lists can be validated at runtime using [`ZStrList::from_bytes_with_nul()`]
+//! or [`ZStrList::from_ptr()`], or built at compile time using the
+//! [`zstr_list!()`] macro.
+
+#![no_std]
+
+use core::fmt::{ self, Debug, Display, Formatter };
+use core::ffi::{ CStr, c_char };
+use core::iter::FusedIterator;
+
+/// Creates a [`ZStrList`] from string or byte string literals at compile
+/// time. The strings must be non-empty and must not contain NUL bytes,
+/// which is checked by [`zstr::zstr_multi!()`]. The resulting expression
+/// can be used in `const` context.
+///
+/// ### Examples:
+///
+/// ```
+/// use zstr_list::{ zstr_list, ZStrList };
+///
+/// const LIST: ZStrList<'static> = zstr_list!("foo", "bar", b"qux");
+/// assert_eq!(LIST.as_bytes(), b"foo\0bar\0qux\0\0");
+///
+/// let items: Vec<_> = LIST.iter().map(|item| item.to_bytes()).collect();
+/// assert_eq!(items, [&b"foo"[..], b"bar", b"qux"]);
+///
+/// assert!(zstr_list!().is_empty());
+/// ```
+///
+/// ```compile_fail
+/// # use zstr_list::zstr_list;
+/// #
+/// let invalid = zstr_list!("foo", "", "bar");
+/// ```
+#[macro_export]
+macro_rules! zstr_list {
+    ($($items:tt)*) => {
+        // SAFETY: `zstr_multi!()` produces a valid list.
+        unsafe {
+            $crate::ZStrList::from_bytes_unchecked($crate::__zstr_multi!($($items)*))
+        }
+    };
+}
+
+#[doc(hidden)]
+pub use zstr::zstr_multi as __zstr_multi;
+
+/// A borrowed, double-NUL-terminated list of C strings.
+///
+/// The underlying buffer always includes the terminating 0 byte of
+/// each item, as well as the terminating 0 byte of the list itself.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ZStrList<'a> {
+    /// The complete list, including every terminator.
+    bytes: &'a [u8],
+}
+
+impl<'a> ZStrList<'a> {
+    /// Validates a double-NUL-terminated list of C strings.
+    ///
+    /// The buffer must end with the terminating 0 byte of the list,
+    /// i.e. there must not be any trailing bytes after it.
+    ///
+    /// ### Examples:
+    ///
+    /// ```
+    /// use zstr_list::{ ZStrList, FromBytesError };
+    ///
+    /// let list = ZStrList::from_bytes_with_nul(b"ab\0c\0\0").unwrap();
+    /// assert_eq!(list.len(), 2);
+    ///
+    /// let empty = ZStrList::from_bytes_with_nul(b"\0").unwrap();
+    /// assert!(empty.is_empty());
+    ///
+    /// assert_eq!(
+    ///     ZStrList::from_bytes_with_nul(b"ab\0c\0"),
+    ///     Err(FromBytesError::NotTerminated),
+    /// );
+    /// assert_eq!(
+    ///     ZStrList::from_bytes_with_nul(b"ab\0\0c\0\0"),
+    ///     Err(FromBytesError::TrailingBytes { position: 4 }),
+    /// );
+    /// ```
+    pub const fn from_bytes_with_nul(bytes: &'a [u8]) -> Result<Self, FromBytesError> {
+        let mut i = 0;
+
+        while i < bytes.len() {
+            if bytes[i] == 0x00 {
+                // This is the terminator of the list itself.
+                return if i + 1 == bytes.len() {
+                    Ok(ZStrList { bytes })
+                } else {
+                    Err(FromBytesError::TrailingBytes { position: i + 1 })
+                };
+            }
+
+            // Skip to the byte after the terminator of the current item.
+            while i < bytes.len() && bytes[i] != 0x00 {
+                i += 1;
+            }
+            i += 1;
+        }
+
+        Err(FromBytesError::NotTerminated)
+    }
+
+    /// Creates a list from a buffer without validating it.
+    ///
+    /// # Safety
+    ///
+    /// `bytes` must be a valid double-NUL-terminated list, i.e. calling
+    /// [`ZStrList::from_bytes_with_nul()`] on it must succeed.
+    pub const unsafe fn from_bytes_unchecked(bytes: &'a [u8]) -> Self {
+        ZStrList { bytes }
+    }
+
+    /// Creates a list from a pointer to a double-NUL-terminated buffer,
+    /// by scanning it for the terminator of the list.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be non-null, and it must point to a double-NUL-terminated
+    /// list that is valid for reads and is not mutated during `'a`. See the
+    /// safety requirements of [`CStr::from_ptr()`], which apply to every
+    /// item of the list as well as to the list as a whole.
+    ///
+    /// ### Examples:
+    ///
+    /// ```
+    /// use zstr_list::ZStrList;
+    ///
+    /// let buffer = b"foo\0bar\0\0garbage";
+    /// let list = unsafe { ZStrList::from_ptr(buffer.as_ptr().cast()) };
+    /// assert_eq!(list.as_bytes(), b"foo\0bar\0\0");
+    /// ```
+    pub unsafe fn from_ptr(ptr: *const c_char) -> Self {
+        let start = ptr.cast::<u8>();
+        let mut len = 0;
+
+        loop {
+            if *start.add(len) == 0x00 {
+                break;
+            }
+
+            // Skip to the byte after the terminator of the current item.
+            while *start.add(len) != 0x00 {
+                len += 1;
+            }
+            len += 1;
+        }
+
+        ZStrList {
+            bytes: core::slice::from_raw_parts(start, len + 1),
+        }
+    }
+
+    /// Returns the underlying buffer, including every terminator.
+    pub const fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns a pointer to the beginning of the underlying buffer.
+    pub const fn as_ptr(&self) -> *const c_char {
+        self.bytes.as_ptr().cast()
+    }
+
+    /// Returns `true` if the list does not contain any strings.
+    pub const fn is_empty(&self) -> bool {
+        self.bytes.len() == 1
+    }
+
+    /// Returns the number of strings in the list.
+    ///
+    /// This is computed by counting the terminators, so it takes time
+    /// proportional to the length of the buffer.
+    pub fn len(&self) -> usize {
+        self.bytes.iter().filter(|&&b| b == 0x00).count() - 1
+    }
+
+    /// Returns an iterator over the strings in the list.
+    pub fn iter(&self) -> Iter<'a> {
+        Iter { rest: self.bytes }
+    }
+}
+
+impl Debug for ZStrList<'_> {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        formatter.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a> IntoIterator for ZStrList<'a> {
+    type Item = &'a CStr;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &ZStrList<'a> {
+    type Item = &'a CStr;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the strings in a [`ZStrList`].
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    /// The rest of the list, including the terminator of the list.
+    rest: &'a [u8],
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a CStr;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let nul = self.rest.iter().position(|&b| b == 0x00)?;
+
+        // An empty item is the terminator of the list.
+        if nul == 0 {
+            return None;
+        }
+
+        let (item, rest) = self.rest.split_at(nul + 1);
+        self.rest = rest;
+
+        // SAFETY: `item` ends with its only NUL byte.
+        Some(unsafe { CStr::from_bytes_with_nul_unchecked(item) })
+    }
+}
+
+impl FusedIterator for Iter<'_> {}
+
+/// The error returned by [`ZStrList::from_bytes_with_nul()`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FromBytesError {
+    /// The buffer ended before the terminator of the list.
+    NotTerminated,
+    /// The buffer continues after the terminator of the list.
+    TrailingBytes {
+        /// The index of the first byte after the terminator.
+        position: usize,
+    },
+}
+
+impl Display for FromBytesError {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            FromBytesError::NotTerminated => {
+                formatter.write_str("string list is not double-NUL-terminated")
+            }
+            FromBytesError::TrailingBytes { position } => {
+                write!(formatter, "string list has trailing bytes after its terminator at index {}", position)
+            }
+        }
+    }
+}